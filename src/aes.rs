@@ -11,22 +11,32 @@ use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::str::FromStr;
 use base58::{FromBase58, ToBase58};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 // aes key used to crypto data
 #[derive(Clone, Eq, PartialEq)]
 pub struct AesKey(GenericArray<u8, U48>);
 
+// 避免密钥被日志打印出来，需要明文的地方使用to_base58_secret
 impl std::fmt::Debug for AesKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_base58())
+        write!(f, "[Protected AesKey]")
     }
 }
 impl std::fmt::Display for AesKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_base58())
+        write!(f, "[Protected AesKey]")
     }
 }
 
+impl Drop for AesKey {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for AesKey {}
+
 impl From<GenericArray<u8, U48>> for AesKey {
     fn from(aes_key: GenericArray<u8, U48>) -> Self {
         Self(aes_key)
@@ -131,29 +141,41 @@ impl AesKey {
         self.0.len()
     }
 
+    #[deprecated(note = "AesKey is secret material, use to_base58_secret instead")]
     pub fn to_base58(&self) -> String {
+        self.to_base58_secret()
+    }
+
+    // 明文输出密钥，调用方需要保证不会被写到日志里
+    pub fn to_base58_secret(&self) -> String {
         self.0.as_slice().to_base58()
     }
 
+    pub fn expose_secret(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    // 错误信息里不能带上输入的字符串，避免泄露密钥
     pub fn from_base58(s: &str) -> BuckyResult<Self> {
         let buf = s.from_base58().map_err(|e| {
             let msg = format!(
-                "convert base58 str to AesKey buf failed, str={}, {:?}",
-                s, e
+                "convert base58 str to AesKey buf failed, len={}, {:?}",
+                s.len(), e
             );
             error!("{}", msg);
             BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
         })?;
+        let buf = zeroize::Zeroizing::new(buf);
 
         if buf.len() != 48 {
             let msg = format!(
-                "convert base58 str to AesKey failed, len unmatch: str={}",
-                s
+                "convert base58 str to AesKey failed, len unmatch: len={}",
+                buf.len()
             );
             return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
         }
 
-        Ok(Self::from(buf))
+        Ok(Self(GenericArray::clone_from_slice(&buf)))
     }
 
     pub fn proxy(n: u64) -> AesKey {
@@ -366,6 +388,19 @@ mod test_aes {
     use generic_array::typenum::U48;
     use generic_array::GenericArray;
 
+    #[test]
+    fn redact() {
+        let aes_key = AesKey::random();
+        assert_eq!(format!("{:?}", aes_key), "[Protected AesKey]");
+        assert_eq!(format!("{}", aes_key), "[Protected AesKey]");
+
+        let s = aes_key.to_base58_secret();
+        let aes_key2 = AesKey::from_base58(&s).unwrap();
+        assert_eq!(aes_key, aes_key2);
+        assert_eq!(aes_key.expose_secret(), aes_key2.expose_secret());
+        assert!(AesKey::from_base58(&s[..10]).is_err());
+    }
+
     #[test]
     fn test() {
         let key = b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0E";
@@ -0,0 +1,299 @@
+use crate::*;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use k256::elliptic_curve::group::Group;
+use k256::elliptic_curve::ops::{LinearCombinationExt, Reduce};
+use k256::elliptic_curve::point::DecompactPoint;
+use k256::elliptic_curve::{Field, PrimeField};
+use k256::sha2::{Digest, Sha256};
+use k256::{AffinePoint, FieldBytes, ProjectivePoint, Scalar, U256};
+use libsecp256k1 as secp256k1;
+use rand::thread_rng;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// 每个线程至少分到的签名数，批量太小时不值得开线程
+const VERIFY_BATCH_MIN_CHUNK: usize = 64;
+
+pub type BatchVerifyItem<'a> = (&'a PublicKey, &'a [u8], &'a Signature);
+
+// 批量校验，返回每个签名各自的校验结果，和逐个调用PublicKey::verify的结果一致
+// schnorr和ed25519签名使用批量校验算法，批量校验失败时再逐个校验找出无效的签名；其余签名并行逐个校验
+pub fn verify_batch(items: &[BatchVerifyItem]) -> Vec<bool> {
+    let failed = AtomicBool::new(false);
+    let chunks = parallel_chunks(items, |chunk| verify_chunk(chunk, false, &failed));
+
+    chunks.into_iter().flatten().collect()
+}
+
+// 批量校验，只有全部签名都有效才返回true；遇到无效签名立即停止，不区分是哪一个
+pub fn verify_batch_all(items: &[BatchVerifyItem]) -> bool {
+    let failed = AtomicBool::new(false);
+    parallel_chunks(items, |chunk| {
+        verify_chunk(chunk, true, &failed);
+    });
+
+    !failed.load(Ordering::Relaxed)
+}
+
+// 按cpu核数把items切块并行处理，返回每块的结果
+fn parallel_chunks<'a, T, F>(items: &'a [BatchVerifyItem<'a>], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&'a [BatchVerifyItem<'a>]) -> T + Sync,
+{
+    let threads = std::thread::available_parallelism().map(|v| v.get()).unwrap_or(1);
+    let chunk_size = std::cmp::max(items.len().div_ceil(threads), VERIFY_BATCH_MIN_CHUNK);
+    if items.len() <= chunk_size {
+        return vec![f(items)];
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items.chunks(chunk_size).map(|chunk| {
+            let f = &f;
+            scope.spawn(move || f(chunk))
+        }).collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+}
+
+// all_or_nothing模式下遇到无效签名会设置failed并提前返回，此时返回的结果不完整
+fn verify_chunk(items: &[BatchVerifyItem], all_or_nothing: bool, failed: &AtomicBool) -> Vec<bool> {
+    let mut results = vec![false; items.len()];
+    let mut schnorr = vec![];
+    let mut ed25519 = vec![];
+
+    for (i, (public_key, data, sign)) in items.iter().enumerate() {
+        if all_or_nothing && failed.load(Ordering::Relaxed) {
+            return results;
+        }
+
+        let hash = hash_data_with_time(data, sign.sign_time());
+        match (public_key, sign.sign()) {
+            (PublicKey::Secp256k1(pk), SignData::Schnorr(_)) => {
+                // 解析失败的签名逐个校验也一定失败
+                if let Some(entry) = SchnorrBatchEntry::parse(pk, &hash, sign) {
+                    schnorr.push((i, entry));
+                    continue;
+                }
+            }
+            (PublicKey::Ed25519(pk), SignData::Ed25519(_)) => {
+                if let Some(entry) = Ed25519BatchEntry::parse(pk, hash, sign) {
+                    ed25519.push((i, entry));
+                    continue;
+                }
+                results[i] = public_key.verify_prehashed(&hash, sign);
+            }
+            _ => {
+                results[i] = public_key.verify_prehashed(&hash, sign);
+            }
+        }
+
+        if all_or_nothing && !results[i] {
+            failed.store(true, Ordering::Relaxed);
+            return results;
+        }
+    }
+
+    if !schnorr.is_empty() {
+        if SchnorrBatchEntry::verify_batch(schnorr.iter().map(|(_, entry)| entry)) {
+            schnorr.iter().for_each(|(i, _)| results[*i] = true);
+        } else if all_or_nothing {
+            failed.store(true, Ordering::Relaxed);
+            return results;
+        } else {
+            for (i, entry) in &schnorr {
+                results[*i] = entry.verify();
+            }
+        }
+    }
+
+    if !ed25519.is_empty() {
+        if Ed25519BatchEntry::verify_batch(ed25519.iter().map(|(_, entry)| entry)) {
+            ed25519.iter().for_each(|(i, _)| results[*i] = true);
+        } else if all_or_nothing {
+            failed.store(true, Ordering::Relaxed);
+            return results;
+        } else {
+            for (i, entry) in &ed25519 {
+                results[*i] = entry.verify();
+            }
+        }
+    }
+
+    results
+}
+
+fn schnorr_tagged_hash(tag: &[u8]) -> Sha256 {
+    let tag_hash = Sha256::digest(tag);
+    let mut digest = Sha256::new();
+    digest.update(tag_hash);
+    digest.update(tag_hash);
+    digest
+}
+
+// BIP340: s*G == R + e*P
+struct SchnorrBatchEntry {
+    r: ProjectivePoint,
+    s: Scalar,
+    e: Scalar,
+    p: ProjectivePoint,
+}
+
+impl SchnorrBatchEntry {
+    fn parse(public_key: &secp256k1::PublicKey, hash: &HashValue, sign: &Signature) -> Option<Self> {
+        let compressed = public_key.serialize_compressed();
+        let px = &compressed[1..];
+        let p: AffinePoint = Option::from(AffinePoint::decompact(FieldBytes::from_slice(px)))?;
+
+        // 和单独校验使用同样的解析规则: r是合法的域元素，s是非零标量
+        let sign = k256::schnorr::Signature::try_from(sign.as_slice()).ok()?.to_bytes();
+        let (r_bytes, s_bytes) = sign.split_at(32);
+        // R取偶数y，x不在曲线上的签名单独校验也不可能通过
+        let r: Option<AffinePoint> = AffinePoint::decompact(FieldBytes::from_slice(r_bytes)).into();
+        let s: Option<Scalar> = Scalar::from_repr(*FieldBytes::from_slice(s_bytes)).into();
+
+        let e = <Scalar as Reduce<U256>>::reduce_bytes(
+            &schnorr_tagged_hash(b"BIP0340/challenge")
+                .chain_update(r_bytes)
+                .chain_update(px)
+                .chain_update(hash.as_slice())
+                .finalize(),
+        );
+
+        Some(Self {
+            r: r?.into(),
+            s: s?,
+            e,
+            p: p.into(),
+        })
+    }
+
+    fn verify(&self) -> bool {
+        let expect = ProjectivePoint::lincomb_ext(&[
+            (ProjectivePoint::GENERATOR, self.s),
+            (self.p, -self.e),
+        ]);
+        expect == self.r
+    }
+
+    // 随机系数a_i(a_0 = 1): (sum a_i*s_i)*G - sum a_i*R_i - sum a_i*e_i*P_i == 0
+    fn verify_batch<'a>(entries: impl Iterator<Item = &'a Self>) -> bool {
+        let mut rng = thread_rng();
+        let mut s_sum = Scalar::ZERO;
+        let mut terms = vec![];
+        for (i, entry) in entries.enumerate() {
+            let a = if i == 0 { Scalar::ONE } else { Scalar::random(&mut rng) };
+            s_sum += a * entry.s;
+            terms.push((entry.r, -a));
+            terms.push((entry.p, -(a * entry.e)));
+        }
+        terms.push((ProjectivePoint::GENERATOR, s_sum));
+
+        bool::from(ProjectivePoint::lincomb_ext(terms.as_slice()).is_identity())
+    }
+}
+
+struct Ed25519BatchEntry {
+    public_key: ed25519_dalek::VerifyingKey,
+    hash: HashValue,
+    sign: ed25519_dalek::Signature,
+}
+
+impl Ed25519BatchEntry {
+    // 批量校验不做verify_strict的小阶点检查，而且混有挠点的签名有一定概率被批量校验误判为有效
+    // 所以只有公钥和R都在素数阶子群里才走批量校验，其余的单独校验
+    fn parse(public_key: &ed25519_dalek::VerifyingKey, hash: HashValue, sign: &Signature) -> Option<Self> {
+        let sign = ed25519_dalek::Signature::from_slice(sign.as_slice()).ok()?;
+
+        let point = public_key.to_edwards();
+        if point.is_small_order() || !point.is_torsion_free() {
+            return None;
+        }
+        let r = CompressedEdwardsY(*sign.r_bytes()).decompress()?;
+        if r.is_small_order() || !r.is_torsion_free() {
+            return None;
+        }
+
+        Some(Self {
+            public_key: *public_key,
+            hash,
+            sign,
+        })
+    }
+
+    fn verify(&self) -> bool {
+        self.public_key.verify_strict(self.hash.as_slice(), &self.sign).is_ok()
+    }
+
+    fn verify_batch<'a>(entries: impl Iterator<Item = &'a Self>) -> bool {
+        let mut messages = vec![];
+        let mut signs = vec![];
+        let mut public_keys = vec![];
+        for entry in entries {
+            messages.push(entry.hash.as_slice());
+            signs.push(entry.sign);
+            public_keys.push(entry.public_key);
+        }
+
+        ed25519_dalek::verify_batch(&messages, &signs, &public_keys).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn batch_verify() {
+        let keys = [
+            PrivateKey::generate_secp256k1().unwrap(),
+            PrivateKey::generate_ed25519().unwrap(),
+            PrivateKey::generate_p256().unwrap(),
+            PrivateKey::generate_rsa(1024).unwrap(),
+        ];
+        let public_keys: Vec<PublicKey> = keys.iter().map(|v| v.public()).collect();
+
+        let mut datas = vec![];
+        let mut signs = vec![];
+        let mut signers = vec![];
+        for i in 0..200usize {
+            let key = i % 4;
+            let data = format!("batch object {}", i).into_bytes();
+            let sign = if key == 0 && i % 8 == 0 {
+                keys[key].sign_schnorr(&data).unwrap()
+            } else {
+                keys[key].sign(&data).unwrap()
+            };
+            datas.push(data);
+            signs.push(sign);
+            signers.push(key);
+        }
+
+        let items: Vec<BatchVerifyItem> = (0..datas.len())
+            .map(|i| (&public_keys[signers[i]], datas[i].as_slice(), &signs[i]))
+            .collect();
+        assert!(verify_batch(&items).iter().all(|v| *v));
+        assert!(verify_batch_all(&items));
+        assert!(verify_batch(&[]).is_empty());
+        assert!(verify_batch_all(&[]));
+
+        // 混入无效签名: 数据不匹配，公钥不匹配，算法不匹配
+        let mut items = items;
+        let other = b"other object".to_vec();
+        items[8].1 = other.as_slice();
+        items[17].0 = &public_keys[2];
+        items[33].1 = other.as_slice();
+        items[102].0 = &public_keys[3];
+        items[199].1 = other.as_slice();
+
+        let results = verify_batch(&items);
+        for (i, ret) in results.iter().enumerate() {
+            assert_eq!(*ret, items[i].0.verify(items[i].1, items[i].2));
+            assert_eq!(*ret, ![8, 17, 33, 102, 199].contains(&i));
+        }
+        assert!(!verify_batch_all(&items));
+        assert!(!verify_batch_all(&items[33..34]));
+        assert!(verify_batch_all(&items[34..100]));
+    }
+}
@@ -0,0 +1,555 @@
+use crate::*;
+
+use base58::{FromBase58, ToBase58};
+use hmac::{Hmac, Mac};
+use libsecp256k1 as secp256k1;
+use ripemd::Ripemd160;
+use rsa::sha2::{Digest, Sha256, Sha512};
+use std::str::FromStr;
+
+type HmacSha512 = Hmac<Sha512>;
+
+// BIP32定义的xprv/xpub的version(mainnet)
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xAD, 0xE4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+
+const EXTENDED_KEY_SIZE: usize = 78;
+const CHAIN_CODE_SIZE: usize = 32;
+const HARDENED_BIT: u32 = 1 << 31;
+
+// 派生路径中的一级，最高位表示hardened
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ChildNumber(u32);
+
+impl ChildNumber {
+    pub fn normal(index: u32) -> BuckyResult<Self> {
+        if index & HARDENED_BIT != 0 {
+            let msg = format!("invalid bip32 child index: {}", index);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+        Ok(Self(index))
+    }
+
+    pub fn hardened(index: u32) -> BuckyResult<Self> {
+        Self::normal(index).map(|v| Self(v.0 | HARDENED_BIT))
+    }
+
+    pub fn is_hardened(&self) -> bool {
+        self.0 & HARDENED_BIT != 0
+    }
+
+    pub fn index(&self) -> u32 {
+        self.0 & !HARDENED_BIT
+    }
+}
+
+impl From<u32> for ChildNumber {
+    fn from(v: u32) -> Self {
+        Self(v)
+    }
+}
+
+impl From<ChildNumber> for u32 {
+    fn from(v: ChildNumber) -> Self {
+        v.0
+    }
+}
+
+impl std::fmt::Display for ChildNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_hardened() {
+            write!(f, "{}'", self.index())
+        } else {
+            write!(f, "{}", self.index())
+        }
+    }
+}
+
+impl FromStr for ChildNumber {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, hardened) = match s.strip_suffix(['\'', 'h', 'H']) {
+            Some(index) => (index, true),
+            None => (s, false),
+        };
+
+        let index = index.parse::<u32>().map_err(|e| {
+            let msg = format!("invalid bip32 child number: {}, {}", s, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })?;
+
+        if hardened {
+            Self::hardened(index)
+        } else {
+            Self::normal(index)
+        }
+    }
+}
+
+// 派生路径，比如m/44'/0'/0'/0/1
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+impl DerivationPath {
+    pub fn as_slice(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl From<Vec<ChildNumber>> for DerivationPath {
+    fn from(v: Vec<ChildNumber>) -> Self {
+        Self(v)
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "m")?;
+        for child in &self.0 {
+            write!(f, "/{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            let msg = format!("invalid bip32 derivation path: {}", s);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let list = parts.map(ChildNumber::from_str).collect::<BuckyResult<Vec<_>>>()?;
+        Ok(Self(list))
+    }
+}
+
+fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).unwrap();
+    for v in data {
+        mac.update(v);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+fn base58check_encode(data: &[u8]) -> String {
+    let checksum = Sha256::digest(Sha256::digest(data));
+    let mut buf = data.to_vec();
+    buf.extend_from_slice(&checksum[..4]);
+    buf.to_base58()
+}
+
+// xprv是私钥，错误信息里不能带输入的内容，只记录原因和长度
+fn base58check_decode(s: &str) -> BuckyResult<Vec<u8>> {
+    let mut buf = s.from_base58().map_err(|e| {
+        let reason = match e {
+            base58::FromBase58Error::InvalidBase58Character(_, pos) => format!("invalid character at {}", pos),
+            base58::FromBase58Error::InvalidBase58Length => "invalid length".to_owned(),
+        };
+        let msg = format!("invalid base58 extended key: {}, len={}", reason, s.len());
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+    })?;
+
+    if buf.len() != EXTENDED_KEY_SIZE + 4 {
+        let msg = format!("invalid extended key length: got={}, expect={}", buf.len(), EXTENDED_KEY_SIZE + 4);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+    }
+
+    let checksum = buf.split_off(EXTENDED_KEY_SIZE);
+    if Sha256::digest(Sha256::digest(&buf))[..4] != checksum[..] {
+        let msg = format!("invalid extended key checksum: len={}", s.len());
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+    }
+
+    Ok(buf)
+}
+
+// 扩展密钥共有的元数据
+#[derive(Clone, Eq, PartialEq)]
+struct ExtendedKeyAttrs {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: [u8; CHAIN_CODE_SIZE],
+}
+
+impl ExtendedKeyAttrs {
+    fn encode(&self, version: &[u8; 4], key_data: &[u8; 33]) -> String {
+        let mut buf = Vec::with_capacity(EXTENDED_KEY_SIZE);
+        buf.extend_from_slice(version);
+        buf.push(self.depth);
+        buf.extend_from_slice(&self.parent_fingerprint);
+        buf.extend_from_slice(&self.child_number.0.to_be_bytes());
+        buf.extend_from_slice(&self.chain_code);
+        buf.extend_from_slice(key_data);
+        base58check_encode(&buf)
+    }
+
+    fn decode(s: &str, version: &[u8; 4]) -> BuckyResult<(Self, [u8; 33])> {
+        let buf = base58check_decode(s)?;
+        if buf[..4] != version[..] {
+            let msg = format!("unsupport extended key version: {}", hex::encode(&buf[..4]));
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+        }
+
+        let attrs = Self {
+            depth: buf[4],
+            parent_fingerprint: buf[5..9].try_into().unwrap(),
+            child_number: ChildNumber(u32::from_be_bytes(buf[9..13].try_into().unwrap())),
+            chain_code: buf[13..45].try_into().unwrap(),
+        };
+
+        if attrs.depth == 0 && (attrs.parent_fingerprint != [0u8; 4] || attrs.child_number.0 != 0) {
+            let msg = "invalid master extended key: parent fingerprint or child number not zero".to_owned();
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+
+        Ok((attrs, buf[45..].try_into().unwrap()))
+    }
+
+    fn child(&self, parent_public: &secp256k1::PublicKey, child_number: ChildNumber, chain_code: &[u8]) -> BuckyResult<Self> {
+        let depth = self.depth.checked_add(1).ok_or_else(|| {
+            let msg = "bip32 derivation depth exceeds 255".to_owned();
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::OutOfLimit, msg)
+        })?;
+
+        Ok(Self {
+            depth,
+            parent_fingerprint: hash160(&parent_public.serialize_compressed())[..4].try_into().unwrap(),
+            child_number,
+            chain_code: chain_code.try_into().unwrap(),
+        })
+    }
+}
+
+fn parse_tweak(il: &[u8]) -> BuckyResult<Secp256k1SecretKey> {
+    // IL >= n的概率低于2^-127，按BIP32的规定这个index不可用
+    secp256k1::SecretKey::parse_slice(il).map(Secp256k1SecretKey::from).map_err(|e| {
+        let msg = format!("invalid bip32 child key, try next index: {:?}", e);
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::CryptoError, msg)
+    })
+}
+
+// BIP32扩展私钥，只支持secp256k1
+#[derive(Clone, Eq, PartialEq)]
+pub struct ExtendedPrivateKey {
+    attrs: ExtendedKeyAttrs,
+    private_key: Secp256k1SecretKey,
+}
+
+// 避免私钥被日志打印出来
+impl std::fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[Protected ExtendedPrivateKey]")
+    }
+}
+
+impl ExtendedPrivateKey {
+    // 从种子生成master key，种子长度需要在16到64字节之间
+    pub fn new_master(seed: &[u8]) -> BuckyResult<Self> {
+        if seed.len() < 16 || seed.len() > 64 {
+            let msg = format!("invalid bip32 seed length: {}", seed.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let i = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let private_key = parse_tweak(&i[..32])?;
+        Ok(Self {
+            attrs: ExtendedKeyAttrs {
+                depth: 0,
+                parent_fingerprint: [0u8; 4],
+                child_number: ChildNumber(0),
+                chain_code: i[32..].try_into().unwrap(),
+            },
+            private_key,
+        })
+    }
+
+    pub fn derive_child(&self, child_number: ChildNumber) -> BuckyResult<Self> {
+        let parent_public = secp256k1::PublicKey::from_secret_key(&self.private_key);
+        let i = if child_number.is_hardened() {
+            hmac_sha512(&self.attrs.chain_code, &[&[0u8], &self.private_key.serialize(), &child_number.0.to_be_bytes()])
+        } else {
+            hmac_sha512(&self.attrs.chain_code, &[&parent_public.serialize_compressed(), &child_number.0.to_be_bytes()])
+        };
+
+        let tweak = parse_tweak(&i[..32])?;
+        let mut private_key = self.private_key.clone();
+        private_key.tweak_add_assign(&tweak).map_err(|e| {
+            let msg = format!("invalid bip32 child key, try next index: {:?}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::CryptoError, msg)
+        })?;
+
+        Ok(Self {
+            attrs: self.attrs.child(&parent_public, child_number, &i[32..])?,
+            private_key,
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> BuckyResult<Self> {
+        let mut key = self.clone();
+        for child_number in path.as_slice() {
+            key = key.derive_child(*child_number)?;
+        }
+        Ok(key)
+    }
+
+    pub fn private_key(&self) -> PrivateKey {
+        PrivateKey::Secp256k1(self.private_key.clone())
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::Secp256k1(secp256k1::PublicKey::from_secret_key(&self.private_key))
+    }
+
+    pub fn extended_public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            attrs: self.attrs.clone(),
+            public_key: secp256k1::PublicKey::from_secret_key(&self.private_key),
+        }
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.attrs.depth
+    }
+
+    pub fn child_number(&self) -> ChildNumber {
+        self.attrs.child_number
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.attrs.chain_code
+    }
+
+    pub fn parent_fingerprint(&self) -> &[u8; 4] {
+        &self.attrs.parent_fingerprint
+    }
+
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.extended_public_key().fingerprint()
+    }
+
+    pub fn to_xprv(&self) -> String {
+        let mut key_data = [0u8; 33];
+        key_data[1..].copy_from_slice(&self.private_key.serialize());
+        self.attrs.encode(&XPRV_VERSION, &key_data)
+    }
+
+    pub fn from_xprv(s: &str) -> BuckyResult<Self> {
+        let (attrs, key_data) = ExtendedKeyAttrs::decode(s, &XPRV_VERSION)?;
+        if key_data[0] != 0 {
+            let msg = format!("invalid xprv key data prefix: {}", key_data[0]);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+
+        let private_key = secp256k1::SecretKey::parse_slice(&key_data[1..]).map(Secp256k1SecretKey::from).map_err(|e| {
+            let msg = format!("invalid xprv private key: {:?}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Ok(Self { attrs, private_key })
+    }
+}
+
+// BIP32扩展公钥，只能做non-hardened的派生
+#[derive(Clone, Eq, PartialEq)]
+pub struct ExtendedPublicKey {
+    attrs: ExtendedKeyAttrs,
+    public_key: secp256k1::PublicKey,
+}
+
+impl std::fmt::Debug for ExtendedPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_xpub())
+    }
+}
+
+impl std::fmt::Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_xpub())
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_xpub(s)
+    }
+}
+
+impl ExtendedPublicKey {
+    pub fn derive_child(&self, child_number: ChildNumber) -> BuckyResult<Self> {
+        if child_number.is_hardened() {
+            let msg = format!("cannot derive hardened child from extended public key: {}", child_number);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let i = hmac_sha512(&self.attrs.chain_code, &[&self.public_key.serialize_compressed(), &child_number.0.to_be_bytes()]);
+        let tweak = parse_tweak(&i[..32])?;
+        let mut public_key = self.public_key;
+        public_key.tweak_add_assign(&tweak).map_err(|e| {
+            let msg = format!("invalid bip32 child key, try next index: {:?}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::CryptoError, msg)
+        })?;
+
+        Ok(Self {
+            attrs: self.attrs.child(&self.public_key, child_number, &i[32..])?,
+            public_key,
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> BuckyResult<Self> {
+        let mut key = self.clone();
+        for child_number in path.as_slice() {
+            key = key.derive_child(*child_number)?;
+        }
+        Ok(key)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::Secp256k1(self.public_key)
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.attrs.depth
+    }
+
+    pub fn child_number(&self) -> ChildNumber {
+        self.attrs.child_number
+    }
+
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.attrs.chain_code
+    }
+
+    pub fn parent_fingerprint(&self) -> &[u8; 4] {
+        &self.attrs.parent_fingerprint
+    }
+
+    // hash160(压缩公钥)的前4个字节
+    pub fn fingerprint(&self) -> [u8; 4] {
+        hash160(&self.public_key.serialize_compressed())[..4].try_into().unwrap()
+    }
+
+    pub fn to_xpub(&self) -> String {
+        self.attrs.encode(&XPUB_VERSION, &self.public_key.serialize_compressed())
+    }
+
+    pub fn from_xpub(s: &str) -> BuckyResult<Self> {
+        let (attrs, key_data) = ExtendedKeyAttrs::decode(s, &XPUB_VERSION)?;
+        let public_key = secp256k1::PublicKey::parse_compressed(&key_data).map_err(|e| {
+            let msg = format!("invalid xpub public key: {:?}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Ok(Self { attrs, public_key })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn derivation_path() {
+        let path = DerivationPath::from_str("m/44'/0'/0'/0/1").unwrap();
+        assert_eq!(path.as_slice().len(), 5);
+        assert!(path.as_slice()[0].is_hardened());
+        assert!(!path.as_slice()[4].is_hardened());
+        assert_eq!(path.as_slice()[4].index(), 1);
+        assert_eq!(path.to_string(), "m/44'/0'/0'/0/1");
+        assert_eq!(DerivationPath::from_str("m/44h/0H").unwrap().to_string(), "m/44'/0'");
+        assert_eq!(DerivationPath::from_str("m").unwrap(), DerivationPath::default());
+
+        assert!(DerivationPath::from_str("44'/0'").is_err());
+        assert!(DerivationPath::from_str("m/x").is_err());
+        assert!(DerivationPath::from_str("m/2147483648").is_err());
+    }
+
+    #[test]
+    fn bip32_vector() {
+        // BIP32 test vector 1
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPrivateKey::new_master(&seed).unwrap();
+        assert_eq!(master.to_xprv(), "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi");
+        assert_eq!(master.extended_public_key().to_xpub(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+
+        let key = master.derive_path(&DerivationPath::from_str("m/0H").unwrap()).unwrap();
+        assert_eq!(key.to_xprv(), "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7");
+        assert_eq!(key.extended_public_key().to_xpub(), "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+        assert_eq!(key.parent_fingerprint(), &master.fingerprint());
+
+        let key = master.derive_path(&DerivationPath::from_str("m/0H/1/2H/2/1000000000").unwrap()).unwrap();
+        assert_eq!(key.depth(), 5);
+        assert_eq!(key.to_xprv(), "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76");
+        assert_eq!(key.extended_public_key().to_xpub(), "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy");
+
+        let xprv = key.to_xprv();
+        assert_eq!(ExtendedPrivateKey::from_xprv(&xprv).unwrap(), key);
+        let xpub = key.extended_public_key().to_xpub();
+        assert_eq!(ExtendedPublicKey::from_str(&xpub).unwrap(), key.extended_public_key());
+    }
+
+    #[test]
+    fn public_derivation() {
+        let master = ExtendedPrivateKey::new_master(&[7u8; 32]).unwrap();
+        let account = master.derive_path(&DerivationPath::from_str("m/44'/0'/0'").unwrap()).unwrap();
+        let path = DerivationPath::from_str("m/0/1").unwrap();
+
+        // 公钥派生的结果和私钥派生的一致
+        let child = account.derive_path(&path).unwrap();
+        let child_pub = account.extended_public_key().derive_path(&path).unwrap();
+        assert_eq!(child.extended_public_key(), child_pub);
+        assert_eq!(child.private_key().public(), child_pub.public_key());
+
+        // 派生出来的私钥可以正常签名
+        let sign = child.private_key().sign(b"bip32").unwrap();
+        assert!(child_pub.public_key().verify(b"bip32", &sign));
+
+        let hardened = ChildNumber::hardened(0).unwrap();
+        assert!(account.extended_public_key().derive_child(hardened).is_err());
+
+        assert!(ExtendedPrivateKey::new_master(&[0u8; 8]).is_err());
+        assert!(ExtendedPrivateKey::from_xprv(&account.extended_public_key().to_xpub()).is_err());
+        let mut xpub = account.extended_public_key().to_xpub();
+        xpub.pop();
+        assert!(ExtendedPublicKey::from_xpub(&xpub).is_err());
+
+        // 错误信息里不能带xprv的内容
+        let xprv = account.to_xprv();
+        let mut bad_checksum = xprv.clone();
+        let last = if bad_checksum.pop() == Some('1') { '2' } else { '1' };
+        bad_checksum.push(last);
+        for s in [bad_checksum, format!("{}0", &xprv[1..]), xprv[..xprv.len() - 1].to_owned()] {
+            let e = ExtendedPrivateKey::from_xprv(&s).unwrap_err();
+            assert!(!e.msg().contains(&s[4..20]));
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::*;
+
+use hmac::{Hmac, Mac};
+use num_bigint_dig::prime::probably_prime;
+use rsa::BigUint;
+use rsa::sha2::Sha512;
+
+const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+// derive_from_seed的算法版本，同一个版本的输出在以后的版本里必须保持不变
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SeedDeriveVersion {
+    V1 = 1,
+}
+
+impl SeedDeriveVersion {
+    // 派生字节流的info: tag || version || len(key_type) || key_type || bits || len(label) || label
+    pub(crate) fn stream(&self, seed: &[u8], label: &str, key_type: &str, bits: usize) -> SeedStream {
+        let mut info = b"bucky seed derive".to_vec();
+        info.push(*self as u8);
+        info.extend_from_slice(&(key_type.len() as u32).to_be_bytes());
+        info.extend_from_slice(key_type.as_bytes());
+        info.extend_from_slice(&(bits as u32).to_be_bytes());
+        info.extend_from_slice(&(label.len() as u32).to_be_bytes());
+        info.extend_from_slice(label.as_bytes());
+        SeedStream::new(seed, &info)
+    }
+}
+
+// 判定素数时Miller-Rabin的轮数，和rsa crate生成密钥时保持一致
+const PRIME_TEST_ROUNDS: usize = 20;
+
+// 从种子确定性的派生字节流: HMAC-SHA512(seed, info || counter)
+// 输出只依赖种子和info，用来生成需要跨版本保持稳定的密钥
+pub(crate) struct SeedStream {
+    mac: Hmac<Sha512>,
+    counter: u32,
+    block: [u8; 64],
+    pos: usize,
+}
+
+impl SeedStream {
+    pub fn new(seed: &[u8], info: &[u8]) -> Self {
+        let mut mac = Hmac::<Sha512>::new_from_slice(seed).unwrap();
+        mac.update(info);
+        Self {
+            mac,
+            counter: 0,
+            block: [0u8; 64],
+            pos: 64,
+        }
+    }
+
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        for v in buf.iter_mut() {
+            if self.pos == self.block.len() {
+                let mut mac = self.mac.clone();
+                mac.update(&self.counter.to_be_bytes());
+                self.block = mac.finalize().into_bytes().into();
+                self.counter += 1;
+                self.pos = 0;
+            }
+            *v = self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl Drop for SeedStream {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.block);
+    }
+}
+
+// 最高两位置1保证p*q恰好是2*bits位，最低位置1保证是奇数
+// bits不是8的倍数时取ceil(bits/8)字节并丢弃多余的低位，是8的倍数时和逐字节置位的结果一致
+fn generate_prime(stream: &mut SeedStream, bits: usize) -> BigUint {
+    let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+    let extra = bits.div_ceil(8) * 8 - bits;
+    let top = BigUint::from(3u32) << (bits - 2);
+    let mut buf = vec![0u8; bits.div_ceil(8)];
+    loop {
+        stream.fill_bytes(&mut buf);
+        let p = (BigUint::from_bytes_be(&buf) >> extra) | &top | BigUint::from(1u32);
+
+        // 要求gcd(e, p - 1) == 1，e是素数所以只需要p mod e != 1
+        if &p % &e == BigUint::from(1u32) {
+            continue;
+        }
+        if probably_prime(&p, PRIME_TEST_ROUNDS) {
+            return p;
+        }
+    }
+}
+
+// 确定性的生成rsa密钥，不依赖rsa crate内部的密钥生成算法
+// 支持的长度和PrivateKey::generate_rsa一致: RSA_MIN_KEY_BITS到RSA_MAX_KEY_BITS之间按字节对齐的任意长度
+pub(crate) fn generate_rsa_from_stream(stream: &mut SeedStream, bits: usize) -> BuckyResult<rsa::RsaPrivateKey> {
+    if bits % 8 != 0 || !(RSA_MIN_KEY_BITS..=RSA_MAX_KEY_BITS).contains(&bits) {
+        let msg = format!("unsupport deterministic rsa bits: {}", bits);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+    }
+
+    let p = generate_prime(stream, bits / 2);
+    let q = loop {
+        let q = generate_prime(stream, bits / 2);
+        if q != p {
+            break q;
+        }
+    };
+
+    let mut key = rsa::RsaPrivateKey::from_p_q(p, q, BigUint::from(RSA_PUBLIC_EXPONENT)).map_err(|e| {
+        let msg = format!("create deterministic rsa key error: {}", e);
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::CryptoError, msg)
+    })?;
+    key.precompute().map_err(|e| {
+        BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+    })?;
+
+    Ok(key)
+}
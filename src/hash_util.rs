@@ -1,4 +1,4 @@
-use crate::{BuckyError, BuckyResult, HashValue};
+use crate::{BuckyError, BuckyResult, HashValue, RawEncode};
 use async_std::io::{ErrorKind, ReadExt};
 use sha2::Digest;
 use std::path::Path;
@@ -9,6 +9,51 @@ pub fn hash_data(data: &[u8]) -> HashValue {
     sha256.result().into()
 }
 
+// 签名摘要: sha256(data || sign_time)，增量计算避免拷贝一份data来追加时间
+pub(crate) fn hash_data_with_time(data: &[u8], sign_time: u64) -> HashValue {
+    let mut time = [0u8; 8];
+    sign_time.raw_encode(&mut time, &None).unwrap();
+
+    let mut sha256 = sha2::Sha256::new();
+    sha256.input(data);
+    sha256.input(time);
+    sha256.result().into()
+}
+
+// 带标签的签名摘要: sha256(tag_hash || tag_hash || parts... || !sign_time)
+// tag_hash = sha256(tag)，和BIP340的tagged hash一样用固定的64字节前缀区分不同用途的摘要
+// 普通签名的摘要以sign_time结尾，这里以按位取反的sign_time结尾，两者的最后8个字节在任何sign_time下都不相同，
+// 而校验时两边用的是签名里的同一个sign_time，所以普通签名的摘要原文不可能和带标签的摘要原文相同
+fn hash_tagged_with_time(tag: &[u8], parts: &[&[u8]], sign_time: u64) -> HashValue {
+    let tag_hash = sha2::Sha256::digest(tag);
+    let mut time = [0u8; 8];
+    (!sign_time).raw_encode(&mut time, &None).unwrap();
+
+    let mut sha256 = sha2::Sha256::new();
+    sha256.input(tag_hash);
+    sha256.input(tag_hash);
+    for part in parts {
+        sha256.input(part);
+    }
+    sha256.input(time);
+    sha256.result().into()
+}
+
+pub(crate) const SIGN_CONTEXT_TAG: &[u8] = b"bucky/sign-context";
+
+// 带上下文标签的签名摘要，context带长度前缀，和data的边界是确定的
+pub(crate) fn hash_data_with_context(context: &[u8], data: &[u8], sign_time: u64) -> HashValue {
+    let context_len = (context.len() as u64).to_be_bytes();
+    hash_tagged_with_time(SIGN_CONTEXT_TAG, &[&context_len, context, data], sign_time)
+}
+
+pub(crate) const SIGN_HASH_TAG: &[u8] = b"bucky/sign-hash";
+
+// 对已经算好的sha256摘要签名时使用的摘要，和直接对摘要字节做普通签名区分开
+pub(crate) fn hash_prehashed_with_time(hash: &HashValue, sign_time: u64) -> HashValue {
+    hash_tagged_with_time(SIGN_HASH_TAG, &[hash.as_slice()], sign_time)
+}
+
 pub async fn hash_stream(reader: &mut (impl ReadExt + Unpin)) -> BuckyResult<(HashValue, u64)> {
     let mut sha256 = sha2::Sha256::new();
     let mut buf = Vec::with_capacity(1024 * 64);
@@ -6,8 +6,18 @@ mod aes;
 mod hash;
 mod hash_util;
 mod signer;
+mod remote_signer;
 mod verifier;
+mod batch_verify;
+mod verify_policy;
+mod standard_signature;
 mod signature;
+mod multi_signature;
+mod shared_secret;
+mod key_encrypt;
+mod bip32;
+mod deterministic;
+mod mnemonic;
 
 pub use self::aes::*;
 pub use hash::*;
@@ -15,8 +25,18 @@ pub use hash_util::*;
 pub use private_key::*;
 pub use public_key::*;
 pub use signer::*;
+pub use remote_signer::*;
 pub use verifier::*;
+pub use batch_verify::*;
+pub use verify_policy::*;
+pub use standard_signature::*;
 pub use signature::*;
+pub use multi_signature::*;
+pub use shared_secret::*;
+pub use key_encrypt::*;
+pub use bip32::*;
+pub use deterministic::*;
+pub use mnemonic::*;
 
 pub use ::aes as raw_aes;
 pub use rsa;
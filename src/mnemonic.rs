@@ -0,0 +1,148 @@
+use crate::*;
+
+use rand::{thread_rng, CryptoRng, Rng};
+use std::str::FromStr;
+
+// BIP39助记词，使用英文词表
+#[derive(Clone, Eq, PartialEq)]
+pub struct Mnemonic(bip39::Mnemonic);
+
+// 助记词等同于私钥，避免被日志打印出来
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[Protected Mnemonic]")
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = BuckyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_phrase(s)
+    }
+}
+
+impl Mnemonic {
+    // word_count只能是12/15/18/21/24
+    pub fn generate(word_count: usize) -> BuckyResult<Self> {
+        let mut rng = thread_rng();
+        Self::generate_by_rng(&mut rng, word_count)
+    }
+
+    pub fn generate_by_rng<R: Rng + CryptoRng>(rng: &mut R, word_count: usize) -> BuckyResult<Self> {
+        if !(12..=24).contains(&word_count) || word_count % 3 != 0 {
+            let msg = format!("invalid mnemonic word count: {}", word_count);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        let mut entropy = vec![0u8; word_count / 3 * 4];
+        rng.fill(entropy.as_mut_slice());
+        Self::from_entropy(&entropy)
+    }
+
+    pub fn from_entropy(entropy: &[u8]) -> BuckyResult<Self> {
+        let mnemonic = bip39::Mnemonic::from_entropy_in(bip39::Language::English, entropy).map_err(|e| {
+            let msg = format!("invalid mnemonic entropy: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+        })?;
+        Ok(Self(mnemonic))
+    }
+
+    // 校验词表和checksum，checksum错误返回InvalidData，其余返回InvalidFormat
+    pub fn from_phrase(phrase: &str) -> BuckyResult<Self> {
+        let mnemonic = bip39::Mnemonic::parse_in(bip39::Language::English, phrase).map_err(|e| {
+            let code = match e {
+                bip39::Error::InvalidChecksum => BuckyErrorCode::InvalidData,
+                _ => BuckyErrorCode::InvalidFormat,
+            };
+            // 不要把助记词本身输出到日志
+            let msg = format!("invalid mnemonic phrase: {}", e);
+            error!("{}", msg);
+            BuckyError::new(code, msg)
+        })?;
+        Ok(Self(mnemonic))
+    }
+
+    pub fn phrase(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.0.word_count()
+    }
+
+    pub fn to_entropy(&self) -> Vec<u8> {
+        self.0.to_entropy()
+    }
+
+    // PBKDF2-HMAC-SHA512, salt为"mnemonic" + passphrase, 2048轮
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        self.0.to_seed(passphrase)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn mnemonic() {
+        // BIP39 test vector
+        let mnemonic = Mnemonic::from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(mnemonic.phrase(), "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
+        assert_eq!(
+            hex::encode(mnemonic.to_seed("TREZOR")),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        for word_count in [12, 24] {
+            let mnemonic = Mnemonic::generate(word_count).unwrap();
+            assert_eq!(mnemonic.word_count(), word_count);
+            let mnemonic2 = Mnemonic::from_phrase(&mnemonic.phrase()).unwrap();
+            assert_eq!(mnemonic, mnemonic2);
+            assert_eq!(mnemonic.to_seed("pass"), mnemonic2.to_seed("pass"));
+            assert_ne!(mnemonic.to_seed("pass"), mnemonic2.to_seed(""));
+        }
+        assert!(Mnemonic::generate(13).is_err());
+
+        let err = Mnemonic::from_phrase("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon").unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidData);
+        let err = Mnemonic::from_phrase("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon buckyos").unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidFormat);
+        assert_eq!(format!("{:?}", mnemonic), "[Protected Mnemonic]");
+    }
+
+    #[test]
+    fn private_key_from_mnemonic() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        // secp256k1使用BIP32的master key, 和xprv的结果一致
+        let sk = PrivateKey::from_mnemonic(phrase, "TREZOR", PrivateKeyType::Secp256k1).unwrap();
+        let seed = Mnemonic::from_phrase(phrase).unwrap().to_seed("TREZOR");
+        assert_eq!(sk, ExtendedPrivateKey::new_master(&seed).unwrap().private_key());
+        assert_ne!(sk, PrivateKey::from_mnemonic(phrase, "", PrivateKeyType::Secp256k1).unwrap());
+
+        // rsa是确定性生成的，和用V1从种子派生的结果一致
+        let sk1 = PrivateKey::from_mnemonic(phrase, "TREZOR", PrivateKeyType::Rsa).unwrap();
+        let sk2 = PrivateKey::from_mnemonic(phrase, "TREZOR", PrivateKeyType::Rsa).unwrap();
+        assert_eq!(sk1, sk2);
+        assert_eq!(sk1, PrivateKey::derive_from_seed_with_version(SeedDeriveVersion::V1, &seed, "bucky mnemonic", PrivateKeyType::Rsa, None).unwrap());
+        assert_eq!(sk1.public().key_size(), CYFS_PRIVTAE_KEY_DEFAULT_RSA_BITS / 8);
+        // 固定的输出，防止生成算法被无意中修改
+        if let PublicKey::Rsa(pk) = sk1.public() {
+            use rsa::traits::PublicKeyParts;
+            assert_eq!(hash_data(&pk.n().to_bytes_be()).to_hex_string(), "13776348bc7a727142ae327cb68a7c2b3a2ba93a7c56a0ca42f68e6a57179396");
+        }
+        let sign = sk1.sign(b"mnemonic").unwrap();
+        assert!(sk2.public().verify(b"mnemonic", &sign));
+
+        for pt in [PrivateKeyType::Ed25519, PrivateKeyType::P256, PrivateKeyType::X25519] {
+            let sk = PrivateKey::from_mnemonic(phrase, "TREZOR", pt).unwrap();
+            assert_eq!(sk.key_type(), pt);
+            assert_eq!(sk, PrivateKey::derive_from_seed_with_version(SeedDeriveVersion::V1, &seed, "bucky mnemonic", pt, None).unwrap());
+        }
+        assert!(PrivateKey::from_mnemonic("abandon", "", PrivateKeyType::Secp256k1).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::*;
+
+// MN多签的签名容器: (公钥在MNPublicKey里的索引, 签名)
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MultiSignature {
+    signs: Vec<(u16, Signature)>,
+}
+
+impl MultiSignature {
+    pub fn new() -> Self {
+        Self { signs: vec![] }
+    }
+
+    pub fn push(&mut self, index: u16, sign: Signature) {
+        self.signs.push((index, sign));
+    }
+
+    pub fn signs(&self) -> &[(u16, Signature)] {
+        &self.signs
+    }
+
+    pub fn len(&self) -> usize {
+        self.signs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signs.is_empty()
+    }
+}
+
+impl From<Vec<(u16, Signature)>> for MultiSignature {
+    fn from(signs: Vec<(u16, Signature)>) -> Self {
+        Self { signs }
+    }
+}
+
+impl RawEncode for MultiSignature {
+    fn raw_measure(&self, purpose: &Option<RawEncodePurpose>) -> Result<usize, BuckyError> {
+        self.signs.raw_measure(purpose)
+    }
+
+    fn raw_encode<'a>(
+        &self,
+        buf: &'a mut [u8],
+        purpose: &Option<RawEncodePurpose>,
+    ) -> Result<&'a mut [u8], BuckyError> {
+        self.signs.raw_encode(buf, purpose)
+    }
+}
+
+impl<'de> RawDecode<'de> for MultiSignature {
+    fn raw_decode(buf: &'de [u8]) -> Result<(Self, &'de [u8]), BuckyError> {
+        let (signs, buf) = Vec::<(u16, Signature)>::raw_decode(buf)?;
+        Ok((Self { signs }, buf))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn multi_signature() {
+        let data = b"group object";
+        let keys: Vec<PrivateKey> = (0..3).map(|_| PrivateKey::generate_secp256k1().unwrap()).collect();
+        let mn = PublicKeyValue::MN((2, keys.iter().map(|v| v.public()).collect()));
+
+        let mut signs = MultiSignature::new();
+        signs.push(0, keys[0].sign(data).unwrap());
+        assert!(!mn.verify(data, &signs));
+        signs.push(2, keys[2].sign(data).unwrap());
+        assert!(mn.verify(data, &signs));
+        assert!(!mn.verify(b"group object2", &signs));
+
+        let buf = signs.to_vec().unwrap();
+        let signs2 = MultiSignature::clone_from_slice(&buf).unwrap();
+        assert_eq!(signs, signs2);
+        assert!(mn.verify(data, &signs2));
+
+        // 同一个签名者重复签名不能凑够阈值
+        let dup = MultiSignature::from(vec![
+            (1, keys[1].sign(data).unwrap()),
+            (1, keys[1].sign(data).unwrap()),
+        ]);
+        assert!(!mn.verify(data, &dup));
+
+        // 越界的索引
+        let mut out_of_range = signs.clone();
+        out_of_range.push(3, keys[1].sign(data).unwrap());
+        assert!(!mn.verify(data, &out_of_range));
+
+        // 签名和索引对应的公钥不匹配
+        let wrong = MultiSignature::from(vec![
+            (0, keys[0].sign(data).unwrap()),
+            (1, keys[2].sign(data).unwrap()),
+        ]);
+        assert!(!mn.verify(data, &wrong));
+
+        // 公钥列表里重复的公钥只算一个签名者
+        let mn_dup = PublicKeyValue::MN((2, vec![keys[0].public(), keys[0].public(), keys[1].public()]));
+        let dup = MultiSignature::from(vec![
+            (0, keys[0].sign(data).unwrap()),
+            (1, keys[0].sign(data).unwrap()),
+        ]);
+        assert!(!mn_dup.verify(data, &dup));
+
+        // 阈值无效
+        assert!(!PublicKeyValue::MN((0, vec![keys[0].public()])).verify(data, &MultiSignature::new()));
+        assert!(!PublicKeyValue::MN((3, vec![keys[0].public(), keys[1].public()])).verify(data, &signs));
+
+        // 单个公钥等同于1-of-1
+        let single = PublicKeyValue::Single(keys[0].public());
+        assert!(single.verify(data, &MultiSignature::from(vec![(0, keys[0].sign(data).unwrap())])));
+        assert!(!single.verify(data, &MultiSignature::from(vec![(1, keys[0].sign(data).unwrap())])));
+        assert!(!single.verify(data, &MultiSignature::new()));
+    }
+}
@@ -1,11 +1,15 @@
 use crate::*;
 
 use rand::thread_rng;
+use base58::ToBase58;
 use std::convert::From;
 use rsa::pkcs1::{DecodeRsaPublicKey, EncodeRsaPublicKey};
 use rsa::traits::PublicKeyParts;
 use libsecp256k1 as secp256k1;
-use rsa::pkcs8::EncodePublicKey;
+use rsa::pkcs8::{AssociatedOid, EncodePublicKey, LineEnding, SubjectPublicKeyInfoRef};
+use rsa::pkcs8::der::Decode;
+use rsa::pkcs8::der::asn1::BitStringRef;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
 
 // RSA
 const RAW_PUBLIC_KEY_RSA_1024_CODE: u8 = 0_u8;
@@ -17,13 +21,67 @@ const RAW_PUBLIC_KEY_RSA_2048_LENGTH: usize = 294;
 const RAW_PUBLIC_KEY_RSA_3072_CODE: u8 = 2_u8;
 const RAW_PUBLIC_KEY_RSA_3072_LENGTH: usize = 422;
 
+// 其余长度的rsa公钥使用变长编码: code | u16长度 | pkcs1 der
+const RAW_PUBLIC_KEY_RSA_CODE: u8 = 3_u8;
+// 4096位模数和rsa crate允许的最大公钥指数下pkcs1 der的最大长度
+const RAW_PUBLIC_KEY_RSA_MAX_DER_LENGTH: usize = 528;
+
 // SECP256K1
 const RAW_PUBLIC_KEY_SECP256K1_CODE: u8 = 10_u8;
 
+// ED25519
+const RAW_PUBLIC_KEY_ED25519_CODE: u8 = 11_u8;
+
+// P256
+const RAW_PUBLIC_KEY_P256_CODE: u8 = 12_u8;
+const P256_COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
+
+// X25519
+const RAW_PUBLIC_KEY_X25519_CODE: u8 = 13_u8;
+const X25519_PUBLIC_KEY_SIZE: usize = 32;
+
+// key id使用的指纹字节数
+pub const PUBLIC_KEY_ID_LENGTH: usize = 8;
+
+// rsa加密使用的填充方式，默认pkcs1 v1.5和旧版本兼容
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum RsaEncryptPadding {
+    #[default]
+    Pkcs1v15,
+    OaepSha256,
+}
+
+impl RsaEncryptPadding {
+    // 单次加密允许的最大明文长度
+    pub fn max_data_len(&self, key_size: usize) -> usize {
+        match self {
+            Self::Pkcs1v15 => key_size.saturating_sub(11),
+            Self::OaepSha256 => key_size.saturating_sub(2 * 32 + 2),
+        }
+    }
+}
+
+// gen_aeskey_and_encrypt输出的格式版本，只影响rsa，secp256k1总是使用ecies
+// V0: pkcs1 v1.5加密的密文，没有头部，和旧版本兼容
+// V1: AESKEY_ENVELOPE_MAGIC | 1 | oaep-sha256加密的密文
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum AesKeyEncryptVersion {
+    #[default]
+    V0 = 0,
+    V1 = 1,
+}
+
+// rsa密文小于n，只有n的最高字节是0xFF时旧格式的密文才可能以这个字节开头
+pub(crate) const AESKEY_ENVELOPE_MAGIC: u8 = 0xFF;
+pub(crate) const AESKEY_ENVELOPE_HEADER_SIZE: usize = 2;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PublicKey {
     Rsa(rsa::RsaPublicKey),
     Secp256k1(secp256k1::PublicKey),
+    Ed25519(ed25519_dalek::VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+    X25519(x25519_dalek::PublicKey),
     Invalid,
 }
 
@@ -38,6 +96,9 @@ impl PublicKey {
         match self {
             Self::Rsa(_) => PrivateKeyType::Rsa.as_str(),
             Self::Secp256k1(_) => PrivateKeyType::Secp256k1.as_str(),
+            Self::Ed25519(_) => PrivateKeyType::Ed25519.as_str(),
+            Self::P256(_) => PrivateKeyType::P256.as_str(),
+            Self::X25519(_) => PrivateKeyType::X25519.as_str(),
             Self::Invalid => "invalid",
         }
     }
@@ -49,7 +110,11 @@ impl PublicKey {
                 // 采用压缩格式存储 33个字节
                 secp256k1::util::COMPRESSED_PUBLIC_KEY_SIZE
             }
-            Self::Invalid => panic!("Should not come here"),
+            Self::Ed25519(_) => ed25519_dalek::PUBLIC_KEY_LENGTH,
+            Self::P256(_) => P256_COMPRESSED_PUBLIC_KEY_SIZE,
+            Self::X25519(_) => X25519_PUBLIC_KEY_SIZE,
+            // 无效公钥没有长度
+            Self::Invalid => 0,
         }
     }
 
@@ -69,18 +134,27 @@ impl PublicKey {
     }
 
     pub fn encrypt_data(&self, data: &[u8]) -> BuckyResult<Vec<u8>> {
+        self.encrypt_data_with_padding(data, RsaEncryptPadding::Pkcs1v15)
+    }
+
+    pub fn encrypt_data_with_padding(&self, data: &[u8], padding: RsaEncryptPadding) -> BuckyResult<Vec<u8>> {
         match self {
             Self::Rsa(public_key) => {
                 let mut rng = thread_rng();
+                let ret = match padding {
+                    RsaEncryptPadding::Pkcs1v15 => public_key.encrypt(&mut rng, rsa::Pkcs1v15Encrypt, data),
+                    RsaEncryptPadding::OaepSha256 => public_key.encrypt(&mut rng, rsa::Oaep::new::<rsa::sha2::Sha256>(), data),
+                };
                 let encrypted_buf =
-                    match public_key.encrypt(&mut rng, rsa::Pkcs1v15Encrypt, data) {
+                    match ret {
                         Ok(v) => v,
                         Err(e) => match e {
                             rsa::errors::Error::MessageTooLong => {
                                 let msg = format!(
-                                    "encrypt data is too long! data len={}, max len={}",
+                                    "encrypt data is too long! data len={}, max len={}, padding={:?}",
                                     data.len(),
-                                    public_key.size() - 11
+                                    padding.max_data_len(public_key.size()),
+                                    padding,
                                 );
                                 error!("{}", msg);
                                 return Err(BuckyError::new(BuckyErrorCode::InvalidData, msg));
@@ -91,28 +165,58 @@ impl PublicKey {
 
                 Ok(encrypted_buf)
             }
-            Self::Secp256k1(_) => {
-                // 目前secp256k1的非对称加解密只支持交换aes_key时候使用
-                let msg = format!("direct encyrpt with private key of secp256 not support!");
+            Self::Secp256k1(public_key) => {
+                if padding != RsaEncryptPadding::Pkcs1v15 {
+                    let msg = format!("direct encyrpt with public key of secp256 not support padding {:?}!", padding);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+                }
+
+                // 和eciespy/eciesjs的默认格式兼容:
+                // 临时公钥(非压缩65字节) | nonce | tag | aes-256-gcm密文, 密钥为hkdf-sha256(临时公钥 || 共享点)
+                ecies::encrypt(&public_key.serialize_compressed(), data).map_err(|e| {
+                    let msg = format!("encrypt secp256k1 ecies data error: {}", e);
+                    error!("{}", msg);
+                    BuckyError::new(BuckyErrorCode::CryptoError, msg)
+                })
+            }
+            Self::Ed25519(_) | Self::P256(_) | Self::X25519(_) => {
+                let msg = format!("direct encyrpt with public key of {} not support!", self.key_type_str());
                 error!("{}", msg);
                 Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
             }
-            PublicKey::Invalid => panic!("Should not come here"),
+            PublicKey::Invalid => Err(Self::invalid_public_key_error()),
         }
     }
 
     pub fn gen_aeskey_and_encrypt(&self) -> BuckyResult<(AesKey, Vec<u8>)> {
+        self.gen_aeskey_and_encrypt_with_version(AesKeyEncryptVersion::V0)
+    }
+
+    pub fn gen_aeskey_and_encrypt_with_version(&self, version: AesKeyEncryptVersion) -> BuckyResult<(AesKey, Vec<u8>)> {
         match self {
             Self::Rsa(pk) => {
                 // 先产生一个临时的aes_key
                 let key = AesKey::random();
 
                 // 使用publicKey对aes_key加密
-                let mut output = Vec::with_capacity(pk.size());
-                unsafe {
-                    output.set_len(pk.size());
+                let output = match version {
+                    AesKeyEncryptVersion::V0 => {
+                        let mut output = Vec::with_capacity(pk.size());
+                        unsafe {
+                            output.set_len(pk.size());
+                        };
+                        self.encrypt(key.as_slice(), &mut output)?;
+                        output
+                    }
+                    AesKeyEncryptVersion::V1 => {
+                        let mut output = Vec::with_capacity(AESKEY_ENVELOPE_HEADER_SIZE + pk.size());
+                        output.push(AESKEY_ENVELOPE_MAGIC);
+                        output.push(version as u8);
+                        output.append(&mut self.encrypt_data_with_padding(key.as_slice(), RsaEncryptPadding::OaepSha256)?);
+                        output
+                    }
                 };
-                self.encrypt(key.as_slice(), &mut output)?;
 
                 Ok((key, output))
             }
@@ -130,51 +234,193 @@ impl PublicKey {
                 let key = AesKey::from(&key);
                 Ok((key, pk_buf.to_vec()))
             }
-            Self::Invalid => panic!("Should not come here"),
+            Self::Ed25519(_) | Self::P256(_) | Self::X25519(_) => {
+                let msg = format!("gen aeskey with public key of {} not support!", self.key_type_str());
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+            Self::Invalid => Err(Self::invalid_public_key_error()),
         }
     }
 
     pub fn verify(&self, data: &[u8], sign: &Signature) -> bool {
-        let create_time = sign.sign_time();
-        let mut data_new = data.to_vec();
-        data_new.resize(data.len() + create_time.raw_measure(&None).unwrap(), 0);
-        create_time
-            .raw_encode(&mut data_new.as_mut_slice()[data.len()..], &None)
-            .unwrap();
+        let hash = hash_data_with_time(data, sign.sign_time());
+        self.verify_prehashed(&hash, sign)
+    }
 
-        match self {
+    // 校验PrivateKey::sign_hash/sign_stream/sign_file生成的签名，hash是数据的sha256摘要
+    pub fn verify_hash(&self, hash: &HashValue, sign: &Signature) -> bool {
+        let hash = hash_prehashed_with_time(hash, sign.sign_time());
+        self.verify_prehashed(&hash, sign)
+    }
+
+    // 校验PrivateKey::sign_with_context生成的签名，context必须和签名时一致
+    pub fn verify_with_context(&self, context: &[u8], data: &[u8], sign: &Signature) -> bool {
+        let hash = hash_data_with_context(context, data, sign.sign_time());
+        self.verify_prehashed(&hash, sign)
+    }
+
+    // hash为hash_data_with_time(data, sign.sign_time())的结果
+    pub(crate) fn verify_prehashed(&self, hash: &HashValue, sign: &Signature) -> bool {
+        self.try_verify_prehashed(hash, sign).is_ok()
+    }
+
+    // 和verify一样，但是失败时返回具体的原因:
+    // NotMatch: 签名算法和公钥类型不匹配，InvalidFormat: 签名格式错误，InvalidSignature: 签名无效
+    // NotSupport: 公钥类型不支持签名，InvalidParam: 无效的公钥
+    pub fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        let hash = hash_data_with_time(data, sign.sign_time());
+        self.try_verify_prehashed(&hash, sign)
+    }
+
+    fn sign_unmatch_error(&self, sign: &Signature) -> BuckyError {
+        let msg = format!("verify with {} public key but got {} signature", self.key_type_str(), sign.sign().sign_type());
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::NotMatch, msg)
+    }
+
+    fn invalid_public_key_error() -> BuckyError {
+        let msg = "invalid public key!".to_owned();
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::InvalidParam, msg)
+    }
+
+    fn invalid_sign_format_error(key_type: &str, e: impl std::fmt::Display) -> BuckyError {
+        let msg = format!("parse {} signature error: {}", key_type, e);
+        error!("{}", msg);
+        BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+    }
+
+    pub(crate) fn try_verify_prehashed(&self, hash: &HashValue, sign: &Signature) -> BuckyResult<()> {
+        let ret = match self {
             Self::Rsa(public_key) => {
-                let hash = hash_data(&data_new);
-                public_key
-                    .verify(
+                let padding = sign.sign().rsa_sign_padding().ok_or_else(|| self.sign_unmatch_error(sign))?;
+                if sign.as_slice().len() != public_key.size() {
+                    let msg = format!("rsa signature length unmatch: key size={}, sign len={}", public_key.size(), sign.as_slice().len());
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+
+                // 按签名里记录的填充方式校验
+                let ret = match padding {
+                    RsaSignPadding::Pkcs1v15 => public_key.verify(
                         rsa::Pkcs1v15Sign::new::<rsa::sha2::Sha256>(),
                         hash.as_slice(),
                         sign.as_slice(),
-                    )
-                    .is_ok()
+                    ),
+                    RsaSignPadding::PssSha256 => public_key.verify(
+                        rsa::Pss::new::<rsa::sha2::Sha256>(),
+                        hash.as_slice(),
+                        sign.as_slice(),
+                    ),
+                };
+                ret.is_ok()
             }
-            Self::Secp256k1(public_key) => {
-                // 生成消息摘要
-                let hash = hash_data(&data_new);
-                assert_eq!(HashValue::len(), secp256k1::util::MESSAGE_SIZE);
-                let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
-
-                // 解析签名段
-                let sign = match secp256k1::Signature::parse_standard_slice(sign.as_slice()) {
-                    Ok(sign) => sign,
-                    Err(e) => {
-                        error!("parse secp256k1 signature error: {}", e);
-                        return false;
+            Self::Secp256k1(public_key) => match sign.sign() {
+                SignData::Schnorr(_) => {
+                    // BIP340使用x-only公钥
+                    let verifying_key = k256::schnorr::VerifyingKey::from_bytes(&public_key.serialize_compressed()[1..]).map_err(|e| {
+                        let msg = format!("parse schnorr public key error: {}", e);
+                        error!("{}", msg);
+                        BuckyError::new(BuckyErrorCode::CryptoError, msg)
+                    })?;
+                    let sign = k256::schnorr::Signature::try_from(sign.as_slice())
+                        .map_err(|e| Self::invalid_sign_format_error("schnorr", e))?;
+
+                    verifying_key.verify_raw(hash.as_slice(), &sign).is_ok()
+                }
+                // recovery id不参与ecdsa校验，要求用它恢复出的公钥就是自己，否则同一个签名可以有多种编码
+                SignData::EccRecoverable(_, recovery_id) => {
+                    let recovery_id = secp256k1::RecoveryId::parse(*recovery_id)
+                        .map_err(|e| Self::invalid_sign_format_error("secp256k1 recovery id", e))?;
+                    let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
+                    let sign = secp256k1::Signature::parse_standard_slice(sign.as_slice())
+                        .map_err(|e| Self::invalid_sign_format_error("secp256k1", e))?;
+
+                    // 恢复出的公钥一定能校验通过这个签名
+                    match secp256k1::recover(&ctx, &sign, &recovery_id) {
+                        Ok(recovered) => !sign.s.is_high() && recovered == *public_key,
+                        Err(_) => false,
                     }
-                };
+                }
+                SignData::Ecc(_) => {
+                    assert_eq!(HashValue::len(), secp256k1::util::MESSAGE_SIZE);
+                    let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
+
+                    // 解析签名段
+                    let sign = secp256k1::Signature::parse_standard_slice(sign.as_slice())
+                        .map_err(|e| Self::invalid_sign_format_error("secp256k1", e))?;
 
-                // 使用公钥进行校验
-                secp256k1::verify(&ctx, &sign, &public_key)
+                    // 使用公钥进行校验
+                    secp256k1::verify(&ctx, &sign, public_key)
+                }
+                _ => return Err(self.sign_unmatch_error(sign)),
+            },
+            Self::Ed25519(public_key) => {
+                if !matches!(sign.sign(), SignData::Ed25519(_)) {
+                    return Err(self.sign_unmatch_error(sign));
+                }
+
+                let sign = ed25519_dalek::Signature::from_slice(sign.as_slice())
+                    .map_err(|e| Self::invalid_sign_format_error("ed25519", e))?;
+
+                public_key.verify_strict(hash.as_slice(), &sign).is_ok()
             }
-            Self::Invalid => panic!("Should not come here"),
+            Self::P256(public_key) => {
+                if !matches!(sign.sign(), SignData::P256(_)) {
+                    return Err(self.sign_unmatch_error(sign));
+                }
+
+                let sign = p256::ecdsa::Signature::from_slice(sign.as_slice())
+                    .map_err(|e| Self::invalid_sign_format_error("p256", e))?;
+
+                public_key.verify_prehash(hash.as_slice(), &sign).is_ok()
+            }
+            Self::X25519(_) => {
+                let msg = format!("verify with public key of {} not support!", self.key_type_str());
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+            Self::Invalid => return Err(Self::invalid_public_key_error()),
+        };
+
+        if ret {
+            Ok(())
+        } else {
+            let msg = format!("verify {} signature failed", sign.sign().sign_type());
+            debug!("{}", msg);
+            Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg))
         }
     }
 
+    // BIP340使用的32字节x-only公钥，只支持secp256k1
+    pub fn to_x_only_bytes(&self) -> BuckyResult<[u8; 32]> {
+        match self {
+            Self::Secp256k1(public_key) => {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&public_key.serialize_compressed()[1..]);
+                Ok(buf)
+            }
+            _ => {
+                let msg = format!("{} public key not support x-only format!", self.key_type_str());
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+        }
+    }
+
+    // 公钥指纹，基于SPKI DER编码计算，和raw编码里rsa的补齐长度无关
+    // Invalid公钥没有spki编码，返回错误
+    pub fn fingerprint(&self) -> BuckyResult<HashValue> {
+        let der = self.to_spki_der()?;
+        Ok(hash_data(&der))
+    }
+
+    // 短格式的key id: 指纹前8个字节的base58编码
+    pub fn key_id(&self) -> BuckyResult<String> {
+        Ok(self.fingerprint()?.as_slice()[..PUBLIC_KEY_ID_LENGTH].to_base58())
+    }
+
     pub fn to_spki_der(&self) -> BuckyResult<Vec<u8>> {
         match self {
             Self::Rsa(pk) => {
@@ -183,12 +429,132 @@ impl PublicKey {
                 })?;
                 Ok(spki.into_vec())
             }
-            Self::Secp256k1(_) => {
-                let msg = format!("secp256k1 public key not support to spki der!");
+            Self::P256(pk) => {
+                let spki = pk.to_public_key_der().map_err(|e| {
+                    BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                })?;
+                Ok(spki.into_vec())
+            }
+            Self::Secp256k1(pk) => {
+                let spki = k256::PublicKey::from_sec1_bytes(&pk.serialize_compressed())
+                    .map_err(|e| {
+                        BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                    })?
+                    .to_public_key_der()
+                    .map_err(|e| {
+                        BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                    })?;
+                Ok(spki.into_vec())
+            }
+            Self::Ed25519(pk) => {
+                let spki = pk.to_public_key_der().map_err(|e| {
+                    BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                })?;
+                Ok(spki.into_vec())
+            }
+            Self::X25519(pk) => {
+                let spki = SubjectPublicKeyInfoRef {
+                    algorithm: rsa::pkcs8::AlgorithmIdentifierRef {
+                        oid: X25519_ALGORITHM_OID,
+                        parameters: None,
+                    },
+                    subject_public_key: BitStringRef::from_bytes(pk.as_bytes()).map_err(|e| {
+                        BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                    })?,
+                };
+                let spki = rsa::pkcs8::Document::try_from(spki).map_err(|e| {
+                    BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                })?;
+                Ok(spki.into_vec())
+            }
+            Self::Invalid => Err(Self::invalid_public_key_error()),
+        }
+    }
+
+    pub fn to_spki_pem(&self) -> BuckyResult<String> {
+        let der = self.to_spki_der()?;
+        rsa::pkcs8::der::pem::encode_string("PUBLIC KEY", LineEnding::LF, &der).map_err(|e| {
+            BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+        })
+    }
+
+    pub fn from_spki_der(der: &[u8]) -> BuckyResult<Self> {
+        let spki = SubjectPublicKeyInfoRef::from_der(der).map_err(|e| {
+            let msg = format!("decode spki public key error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        let (oid, parameters) = spki.algorithm.oids().map_err(|e| {
+            let msg = format!("decode spki public key algorithm error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        let ret = if oid == rsa::pkcs1::ALGORITHM_OID {
+            rsa::RsaPublicKey::try_from(spki).map(Self::Rsa)
+        } else if oid == k256::elliptic_curve::ALGORITHM_OID && parameters == Some(k256::Secp256k1::OID) {
+            k256::PublicKey::try_from(spki).map(|public_key| {
+                let key_buf = public_key.to_sec1_bytes();
+                Self::Secp256k1(secp256k1::PublicKey::parse_slice(&key_buf, None).unwrap())
+            })
+        } else if oid == p256::elliptic_curve::ALGORITHM_OID && parameters == Some(p256::NistP256::OID) {
+            p256::ecdsa::VerifyingKey::try_from(spki).map(Self::P256)
+        } else if oid == ed25519_dalek::pkcs8::ALGORITHM_OID {
+            ed25519_dalek::VerifyingKey::try_from(spki).map(Self::Ed25519)
+        } else if oid == X25519_ALGORITHM_OID {
+            spki.subject_public_key
+                .as_bytes()
+                .and_then(|v| <[u8; 32]>::try_from(v).ok())
+                .map(|v| Self::X25519(x25519_dalek::PublicKey::from(v)))
+                .ok_or(rsa::pkcs8::spki::Error::KeyMalformed)
+        } else {
+            let msg = format!("unsupport spki public key algorithm: {}, parameters={:?}", oid, parameters);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+        };
+
+        ret.map_err(|e| {
+            let msg = format!("decode spki public key error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+
+    pub fn from_pkcs1_der(der: &[u8]) -> BuckyResult<Self> {
+        let pk = rsa::RsaPublicKey::from_pkcs1_der(der).map_err(|e| {
+            let msg = format!("decode pkcs1 rsa public key error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+        Ok(Self::Rsa(pk))
+    }
+
+    // 自动识别SPKI/PKCS#1(rsa)格式的DER公钥
+    pub fn from_der(der: &[u8]) -> BuckyResult<Self> {
+        if SubjectPublicKeyInfoRef::from_der(der).is_ok() {
+            Self::from_spki_der(der)
+        } else {
+            Self::from_pkcs1_der(der)
+        }
+    }
+
+    // 根据pem的label自动识别SPKI/PKCS#1(rsa)格式
+    pub fn from_pem(pem: &str) -> BuckyResult<Self> {
+        let (label, der) = rsa::pkcs8::der::pem::decode_vec(pem.as_bytes()).map_err(|e| {
+            let msg = format!("decode pem error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        match label {
+            "PUBLIC KEY" => Self::from_spki_der(&der),
+            "RSA PUBLIC KEY" => Self::from_pkcs1_der(&der),
+            _ => {
+                let msg = format!("unsupport public key pem label: {}", label);
                 error!("{}", msg);
                 Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
             }
-            Self::Invalid => panic!("Should not come here"),
         }
     }
 
@@ -205,7 +571,12 @@ impl PublicKey {
                 error!("{}", msg);
                 Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
             }
-            Self::Invalid => panic!("Should not come here"),
+            Self::Ed25519(_) | Self::P256(_) | Self::X25519(_) => {
+                let msg = format!("{} public key not support to pkcs1 der!", self.key_type_str());
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotSupport, msg))
+            }
+            Self::Invalid => Err(Self::invalid_public_key_error()),
         }
     }
 }
@@ -216,7 +587,33 @@ impl RawFixedBytes for PublicKey {
     }
 
     fn raw_max_bytes() -> Option<usize> {
-        Some(1 + RAW_PUBLIC_KEY_RSA_3072_LENGTH)
+        Some(1 + 2 + RAW_PUBLIC_KEY_RSA_MAX_DER_LENGTH)
+    }
+}
+
+impl PublicKey {
+    // 1024/2048/3072位的rsa公钥使用定长编码, 返回(code, 编码后的总长度)
+    fn rsa_fixed_raw_code(pk: &rsa::RsaPublicKey) -> Option<(u8, usize)> {
+        match pk.size() {
+            // 1024 bits, 128 bytes
+            RSA_KEY_BYTES => Some((RAW_PUBLIC_KEY_RSA_1024_CODE, RAW_PUBLIC_KEY_RSA_1024_LENGTH + 1)),
+            RSA2048_KEY_BYTES => Some((RAW_PUBLIC_KEY_RSA_2048_CODE, RAW_PUBLIC_KEY_RSA_2048_LENGTH + 1)),
+            RSA3072_KEY_BYTES => Some((RAW_PUBLIC_KEY_RSA_3072_CODE, RAW_PUBLIC_KEY_RSA_3072_LENGTH + 1)),
+            _ => None,
+        }
+    }
+
+    fn rsa_raw_der(pk: &rsa::RsaPublicKey) -> BuckyResult<Vec<u8>> {
+        let der = pk.to_pkcs1_der().map_err(|e| {
+            BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+        })?.into_vec();
+        if der.len() > RAW_PUBLIC_KEY_RSA_MAX_DER_LENGTH {
+            let msg = format!("invalid rsa public key length! bits={}, der len={}", pk.n().bits(), der.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg));
+        }
+
+        Ok(der)
     }
 }
 
@@ -224,19 +621,15 @@ impl RawEncode for PublicKey {
     fn raw_measure(&self, _purpose: &Option<RawEncodePurpose>) -> Result<usize, BuckyError> {
         match self {
             Self::Rsa(ref pk) => {
-                match pk.size() {
-                    // 1024 bits, 128 bytes
-                    RSA_KEY_BYTES => Ok(RAW_PUBLIC_KEY_RSA_1024_LENGTH + 1),
-                    RSA2048_KEY_BYTES => Ok(RAW_PUBLIC_KEY_RSA_2048_LENGTH + 1),
-                    RSA3072_KEY_BYTES => Ok(RAW_PUBLIC_KEY_RSA_3072_LENGTH + 1),
-                    len @ _ => {
-                        let msg = format!("invalid rsa public key length! {}", len);
-                        error!("{}", msg);
-                        Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
-                    }
+                match Self::rsa_fixed_raw_code(pk) {
+                    Some((_, len)) => Ok(len),
+                    None => Ok(1 + 2 + Self::rsa_raw_der(pk)?.len()),
                 }
             }
             Self::Secp256k1(_) => Ok(secp256k1::util::COMPRESSED_PUBLIC_KEY_SIZE + 1),
+            Self::Ed25519(_) => Ok(ed25519_dalek::PUBLIC_KEY_LENGTH + 1),
+            Self::P256(_) => Ok(P256_COMPRESSED_PUBLIC_KEY_SIZE + 1),
+            Self::X25519(_) => Ok(X25519_PUBLIC_KEY_SIZE + 1),
             Self::Invalid => {
                 let msg = format!("invalid publicKey!");
                 error!("{}", msg);
@@ -249,31 +642,32 @@ impl RawEncode for PublicKey {
     fn raw_encode<'a>(
         &self,
         buf: &'a mut [u8],
-        _purpose: &Option<RawEncodePurpose>,
+        purpose: &Option<RawEncodePurpose>,
     ) -> Result<&'a mut [u8], BuckyError> {
         match self {
             Self::Rsa(ref pk) => {
-                let (code, len) = {
-                    match pk.size() {
-                        RSA_KEY_BYTES => Ok((
-                            RAW_PUBLIC_KEY_RSA_1024_CODE,
-                            RAW_PUBLIC_KEY_RSA_1024_LENGTH + 1,
-                        )),
-                        RSA2048_KEY_BYTES => Ok((
-                            RAW_PUBLIC_KEY_RSA_2048_CODE,
-                            RAW_PUBLIC_KEY_RSA_2048_LENGTH + 1,
-                        )),
-                        RSA3072_KEY_BYTES => Ok((
-                            RAW_PUBLIC_KEY_RSA_3072_CODE,
-                            RAW_PUBLIC_KEY_RSA_3072_LENGTH + 1,
-                        )),
-                        len @ _ => {
-                            let msg = format!("invalid rsa public key length! {}", len);
+                let (code, len) = match Self::rsa_fixed_raw_code(pk) {
+                    Some(v) => v,
+                    None => {
+                        let der = Self::rsa_raw_der(pk)?;
+                        let len = 1 + 2 + der.len();
+                        if buf.len() < len {
+                            let msg = format!(
+                                "not enough buffer for encode rsa PublicKey, except={}, got={}",
+                                len,
+                                buf.len()
+                            );
                             error!("{}", msg);
-                            Err(BuckyError::new(BuckyErrorCode::InvalidParam, msg))
+
+                            return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
                         }
+
+                        let buf = RAW_PUBLIC_KEY_RSA_CODE.raw_encode(buf, purpose)?;
+                        let buf = (der.len() as u16).raw_encode(buf, purpose)?;
+                        buf[..der.len()].copy_from_slice(&der);
+                        return Ok(&mut buf[der.len()..]);
                     }
-                }?;
+                };
                 if buf.len() < len {
                     let msg = format!(
                         "not enough buffer for encode privateKey, except={}, got={}",
@@ -305,7 +699,63 @@ impl RawEncode for PublicKey {
 
                 Ok(&mut buf[total_len..])
             }
-            Self::Invalid => panic!("should not reach here"),
+            Self::Ed25519(public_key) => {
+                let total_len = ed25519_dalek::PUBLIC_KEY_LENGTH + 1;
+                if buf.len() < total_len {
+                    let msg = format!(
+                        "not enough buffer for encode ed25519 PublicKey, except={}, got={}",
+                        total_len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                buf[0] = RAW_PUBLIC_KEY_ED25519_CODE;
+                buf[1..total_len].copy_from_slice(public_key.as_bytes());
+
+                Ok(&mut buf[total_len..])
+            }
+            Self::P256(public_key) => {
+                let total_len = P256_COMPRESSED_PUBLIC_KEY_SIZE + 1;
+                if buf.len() < total_len {
+                    let msg = format!(
+                        "not enough buffer for encode p256 PublicKey, except={}, got={}",
+                        total_len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                // 和secp256k1一样采用压缩格式存储
+                buf[0] = RAW_PUBLIC_KEY_P256_CODE;
+                let key_buf = public_key.to_encoded_point(true);
+                buf[1..total_len].copy_from_slice(key_buf.as_bytes());
+
+                Ok(&mut buf[total_len..])
+            }
+            Self::X25519(public_key) => {
+                let total_len = X25519_PUBLIC_KEY_SIZE + 1;
+                if buf.len() < total_len {
+                    let msg = format!(
+                        "not enough buffer for encode x25519 PublicKey, except={}, got={}",
+                        total_len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                buf[0] = RAW_PUBLIC_KEY_X25519_CODE;
+                buf[1..total_len].copy_from_slice(public_key.as_bytes());
+
+                Ok(&mut buf[total_len..])
+            }
+            Self::Invalid => Err(Self::invalid_public_key_error()),
         }
     }
 }
@@ -363,6 +813,32 @@ impl<'de> RawDecode<'de> for PublicKey {
                 })?;
                 Ok((PublicKey::Rsa(pk), &buf[len..]))
             }
+            RAW_PUBLIC_KEY_RSA_CODE => {
+                let (len, buf) = u16::raw_decode(&buf[1..])?;
+                let len = len as usize;
+                if buf.len() < len {
+                    let msg = format!(
+                        "not enough buffer for decode rsa PublicKey, except={}, got={}",
+                        len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                let pk = rsa::RsaPublicKey::from_pkcs1_der(&buf[..len]).map_err(|e| {
+                    BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                })?;
+                // 1024/2048/3072位的公钥只能使用定长编码，保证每个公钥只有一种编码
+                if Self::rsa_fixed_raw_code(&pk).is_some() {
+                    let msg = format!("rsa public key of {} bits must use fixed length encoding", pk.size() * 8);
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+                Ok((PublicKey::Rsa(pk), &buf[len..]))
+            }
             RAW_PUBLIC_KEY_SECP256K1_CODE => {
                 let len = secp256k1::util::COMPRESSED_PUBLIC_KEY_SIZE + 1;
                 if buf.len() < len {
@@ -386,6 +862,68 @@ impl<'de> RawDecode<'de> for PublicKey {
                     }
                 }
             }
+            RAW_PUBLIC_KEY_ED25519_CODE => {
+                let len = ed25519_dalek::PUBLIC_KEY_LENGTH + 1;
+                if buf.len() < len {
+                    let msg = format!(
+                        "not enough buffer for decode ed25519 PublicKey, except={}, got={}",
+                        len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                match ed25519_dalek::VerifyingKey::from_bytes((&buf[1..len]).try_into().unwrap()) {
+                    Ok(public_key) => Ok((PublicKey::Ed25519(public_key), &buf[len..])),
+                    Err(e) => {
+                        let msg = format!("parse ed25519 public key error: {}", e);
+                        error!("{}", msg);
+
+                        Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+                    }
+                }
+            }
+            RAW_PUBLIC_KEY_P256_CODE => {
+                let len = P256_COMPRESSED_PUBLIC_KEY_SIZE + 1;
+                if buf.len() < len {
+                    let msg = format!(
+                        "not enough buffer for decode p256 PublicKey, except={}, got={}",
+                        len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                match p256::ecdsa::VerifyingKey::from_sec1_bytes(&buf[1..len]) {
+                    Ok(public_key) => Ok((PublicKey::P256(public_key), &buf[len..])),
+                    Err(e) => {
+                        let msg = format!("parse p256 public key error: {}", e);
+                        error!("{}", msg);
+
+                        Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg))
+                    }
+                }
+            }
+            RAW_PUBLIC_KEY_X25519_CODE => {
+                let len = X25519_PUBLIC_KEY_SIZE + 1;
+                if buf.len() < len {
+                    let msg = format!(
+                        "not enough buffer for decode x25519 PublicKey, except={}, got={}",
+                        len,
+                        buf.len()
+                    );
+                    error!("{}", msg);
+
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+
+                let key_buf: [u8; X25519_PUBLIC_KEY_SIZE] = buf[1..len].try_into().unwrap();
+                Ok((PublicKey::X25519(x25519_dalek::PublicKey::from(key_buf)), &buf[len..]))
+            }
             v @ _ => Err(BuckyError::new(
                 BuckyErrorCode::InvalidData,
                 &format!("invalid public key type code {}", v),
@@ -415,6 +953,51 @@ impl PublicKeyValue {
             Self::MN(v) => PublicKeyRef::MN(v),
         }
     }
+
+    // 校验多签，Single等同于1-of-1
+    // 所有签名都必须有效，索引不能越界或重复，不同签名者的数量必须达到阈值
+    pub fn verify(&self, data: &[u8], signs: &MultiSignature) -> bool {
+        let (threshold, keys) = match self {
+            Self::Single(key) => (1, std::slice::from_ref(key)),
+            Self::MN((threshold, keys)) => (*threshold as usize, keys.as_slice()),
+        };
+        if threshold == 0 || threshold > keys.len() {
+            error!("invalid mn public key threshold: {}, key count={}", threshold, keys.len());
+            return false;
+        }
+
+        let mut signers: Vec<&PublicKey> = Vec::with_capacity(signs.len());
+        let mut indexes = std::collections::HashSet::with_capacity(signs.len());
+        for (index, sign) in signs.signs() {
+            let key = match keys.get(*index as usize) {
+                Some(key) => key,
+                None => {
+                    error!("multi signature key index out of range: {}, key count={}", index, keys.len());
+                    return false;
+                }
+            };
+            if !indexes.insert(*index) {
+                error!("duplicate multi signature key index: {}", index);
+                return false;
+            }
+            if !key.verify(data, sign) {
+                error!("verify multi signature failed! index={}", index);
+                return false;
+            }
+
+            // 公钥列表里重复的公钥只算一个签名者
+            if !signers.contains(&key) {
+                signers.push(key);
+            }
+        }
+
+        if signers.len() < threshold {
+            error!("multi signature not reach threshold: {}, got={}", threshold, signers.len());
+            return false;
+        }
+
+        true
+    }
 }
 
 impl<'a> From<&'a PublicKey> for PublicKeyRef<'a> {
@@ -526,7 +1109,7 @@ impl<'r> RawEncode for PublicKeyRef<'r> {
 mod test {
     use rsa::pkcs1::der::{Encode, EncodePem};
     use rsa::pkcs1::LineEnding;
-    use crate::{PrivateKey, PublicKey, RawConvertTo, RawDecode};
+    use crate::{hash_data, BuckyErrorCode, PrivateKey, PrivateKeyType, PublicKey, RawConvertTo, RawDecode, RawFixedBytes, SignData, Signature, VerifyPolicy};
 
     #[test]
     fn public_key() {
@@ -555,6 +1138,120 @@ mod test {
         assert!(buf.len() == 0);
 
         assert_eq!(sk1.public(), pk2);
+
+        let sk1 = PrivateKey::generate_ed25519().unwrap();
+        let pk1_buf = sk1.public().to_vec().unwrap();
+        let (pk2, buf) = PublicKey::raw_decode(&pk1_buf).unwrap();
+        assert_eq!(buf.len(), 0);
+        assert_eq!(sk1.public(), pk2);
+
+        let sk1 = PrivateKey::generate_p256().unwrap();
+        let pk1_buf = sk1.public().to_vec().unwrap();
+        assert_eq!(pk1_buf.len(), sk1.public().key_size() + 1);
+        let (pk2, buf) = PublicKey::raw_decode(&pk1_buf).unwrap();
+        assert_eq!(buf.len(), 0);
+        assert_eq!(sk1.public(), pk2);
+
+        let sk1 = PrivateKey::generate_x25519().unwrap();
+        let pk1_buf = sk1.public().to_vec().unwrap();
+        assert_eq!(pk1_buf.len(), sk1.public().key_size() + 1);
+        let (pk2, buf) = PublicKey::raw_decode(&pk1_buf).unwrap();
+        assert_eq!(buf.len(), 0);
+        assert_eq!(sk1.public(), pk2);
+    }
+
+    #[test]
+    fn rsa_raw_length() {
+        // 1024/2048/3072保持原来的定长编码
+        let sk1 = PrivateKey::generate_rsa(1024).unwrap();
+        let pk1_buf = sk1.public().to_vec().unwrap();
+        assert_eq!(pk1_buf[0], 0);
+        assert_eq!(pk1_buf.len(), 163);
+
+        // 其余长度使用变长编码
+        for bits in [1536, 4096] {
+            let sk1 = PrivateKey::generate_rsa(bits).unwrap();
+            let der = sk1.public().to_pkcs1_der().unwrap();
+            let pk1_buf = sk1.public().to_vec().unwrap();
+            assert_eq!(pk1_buf[0], 3);
+            assert_eq!(pk1_buf.len(), 1 + 2 + der.len());
+            assert!(pk1_buf.len() <= PublicKey::raw_max_bytes().unwrap());
+
+            let (pk2, buf) = PublicKey::raw_decode(&pk1_buf).unwrap();
+            assert_eq!(buf.len(), 0);
+            assert_eq!(sk1.public(), pk2);
+            assert_eq!(pk2.key_size(), bits / 8);
+            assert!(PublicKey::raw_decode(&pk1_buf[..pk1_buf.len() - 1]).is_err());
+        }
+
+        // 定长的长度不能使用变长编码
+        let der = sk1.public().to_pkcs1_der().unwrap();
+        let mut buf = vec![3u8];
+        buf.extend_from_slice(&(der.len() as u16).to_vec().unwrap());
+        buf.extend_from_slice(&der);
+        let err = PublicKey::raw_decode(&buf).unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::InvalidFormat);
+
+        assert!(PrivateKey::generate_rsa(1000).is_err());
+        assert!(PrivateKey::generate_rsa(8192).is_err());
+    }
+
+    #[test]
+    fn spki() {
+        let keys = vec![
+            PrivateKey::generate_rsa(1024).unwrap(),
+            PrivateKey::generate_secp256k1().unwrap(),
+            PrivateKey::generate_ed25519().unwrap(),
+            PrivateKey::generate_p256().unwrap(),
+            PrivateKey::generate_x25519().unwrap(),
+        ];
+        for sk in keys {
+            let pk = sk.public();
+            let der = pk.to_spki_der().unwrap();
+            assert_eq!(PublicKey::from_spki_der(&der).unwrap(), pk);
+            assert_eq!(PublicKey::from_der(&der).unwrap(), pk);
+
+            let pem = pk.to_spki_pem().unwrap();
+            assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----"));
+            assert_eq!(PublicKey::from_pem(&pem).unwrap(), pk);
+        }
+
+        // PKCS#1格式的rsa公钥
+        let pk = PrivateKey::generate_rsa(1024).unwrap().public();
+        let der = pk.to_pkcs1_der().unwrap();
+        assert_eq!(PublicKey::from_pkcs1_der(&der).unwrap(), pk);
+        assert_eq!(PublicKey::from_der(&der).unwrap(), pk);
+        let pem = rsa::pkcs8::der::pem::encode_string("RSA PUBLIC KEY", LineEnding::LF, &der).unwrap();
+        assert_eq!(PublicKey::from_pem(&pem).unwrap(), pk);
+
+        assert!(PublicKey::from_der(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn fingerprint() {
+        let seed: Vec<u8> = (0..32).collect();
+        let pk = PrivateKey::derive_from_seed(&seed, "device/1", PrivateKeyType::Secp256k1, None).unwrap().public();
+        assert_eq!(pk.fingerprint().unwrap().to_hex_string(), "6a287a7062e2e2b540dba48e9ad626da524dee3317a4d8288405e4bab03108ec");
+        assert_eq!(pk.key_id().unwrap(), "JksGfhbv5bA");
+
+        let keys = vec![
+            PrivateKey::generate_rsa(1024).unwrap(),
+            PrivateKey::generate_secp256k1().unwrap(),
+            PrivateKey::generate_ed25519().unwrap(),
+            PrivateKey::generate_p256().unwrap(),
+            PrivateKey::generate_x25519().unwrap(),
+        ];
+        for sk in keys {
+            let pk = sk.public();
+            assert_eq!(pk.fingerprint().unwrap(), hash_data(&pk.to_spki_der().unwrap()));
+            let (pk2, _) = PublicKey::raw_decode(&pk.to_vec().unwrap()).unwrap();
+            assert_eq!(pk.fingerprint().unwrap(), pk2.fingerprint().unwrap());
+            assert_eq!(pk.key_id().unwrap(), pk2.key_id().unwrap());
+            assert_ne!(pk.fingerprint().unwrap(), pk.to_vec().map(|v| hash_data(&v)).unwrap());
+        }
+
+        assert!(PublicKey::Invalid.fingerprint().is_err());
+        assert!(PublicKey::Invalid.key_id().is_err());
     }
 
     #[cfg(feature = "x509")]
@@ -568,5 +1265,72 @@ mod test {
         let cert2 = pk1.gen_leaf_certificate("CN=World domination corporation", "CN=World domination corporation,O=World domination Inc,C=US", 365, pk2.public().to_spki_der().unwrap().as_slice()).unwrap();
         let str = cert2.to_pem(LineEnding::LF).unwrap();
         println!("{}", str);
+
+        let pk3 = PrivateKey::generate_p256().unwrap();
+        let cert3 = pk1.gen_leaf_certificate("CN=World domination corporation", "CN=World domination corporation,O=World domination Inc,C=US", 365, pk3.public().to_spki_der().unwrap().as_slice()).unwrap();
+        assert_eq!(cert3.tbs_certificate.subject_public_key_info.to_der().unwrap(), pk3.public().to_spki_der().unwrap());
+
+        let cert4 = pk3.gen_leaf_certificate("CN=World domination corporation", "CN=World domination corporation,O=World domination Inc,C=US", 365, pk2.public().to_spki_der().unwrap().as_slice()).unwrap();
+        let pem = cert4.to_pem(LineEnding::LF).unwrap();
+        let cert = <x509_cert::Certificate as x509_cert::der::DecodePem>::from_pem(pem.as_bytes()).unwrap();
+        assert_eq!(cert, cert4);
+        assert_eq!(cert.tbs_certificate.subject_public_key_info.to_der().unwrap(), pk2.public().to_spki_der().unwrap());
+
+        // p256签发的证书用p256的公钥校验
+        let tbs = cert.tbs_certificate.to_der().unwrap();
+        let sign = crate::StandardSignature::from_der(cert.signature.raw_bytes()).unwrap();
+        assert!(pk3.public().verify_standard(&tbs, &sign));
+        assert!(!pk1.public().verify_standard(&tbs, &sign));
+    }
+
+    #[test]
+    fn try_verify() {
+        let data = b"gateway request";
+        let rsa = PrivateKey::generate_rsa(1024).unwrap();
+        let secp = PrivateKey::generate_secp256k1().unwrap();
+        let ed = PrivateKey::generate_ed25519().unwrap();
+
+        for sk in [&rsa, &secp, &ed] {
+            let sign = sk.sign(data).unwrap();
+            assert!(sk.public().try_verify(data, &sign).is_ok());
+            assert_eq!(sk.public().try_verify(b"other", &sign).unwrap_err().code(), BuckyErrorCode::InvalidSignature);
+        }
+
+        // 算法不匹配
+        let sign = secp.sign(data).unwrap();
+        assert_eq!(rsa.public().try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::NotMatch);
+        assert_eq!(ed.public().try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::NotMatch);
+        let sign = ed.sign(data).unwrap();
+        assert_eq!(secp.public().try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::NotMatch);
+
+        // 签名格式错误
+        let sign = rsa.sign(data).unwrap();
+        let sign = Signature::new(sign.sign_time(), SignData::Rsa(sign.as_slice()[1..].to_vec()));
+        assert_eq!(rsa.public().try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::InvalidFormat);
+        let sign = secp.sign(data).unwrap();
+        // r和s都超出了曲线的阶
+        let sign = Signature::new(sign.sign_time(), SignData::Ecc(generic_array::GenericArray::from([u32::MAX; 16])));
+        assert_eq!(secp.public().try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::InvalidFormat);
+        assert!(!secp.public().verify(data, &sign));
+
+        // 过期
+        let sign = secp.sign(data).unwrap();
+        let policy = VerifyPolicy::new().max_age(std::time::Duration::from_secs(1)).now(sign.sign_time() + 2 * 1000 * 1000);
+        assert_eq!(secp.public().try_verify_with_policy(data, &sign, &policy).unwrap_err().code(), BuckyErrorCode::Expired);
+        assert_eq!(secp.public().try_verify_with_policy(b"other", &sign, &policy).unwrap_err().code(), BuckyErrorCode::InvalidSignature);
+
+        // 不支持签名的公钥和无效公钥都不会panic
+        let x25519 = PrivateKey::generate_x25519().unwrap().public();
+        assert_eq!(x25519.try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::NotSupport);
+        let invalid = PublicKey::Invalid;
+        assert!(!invalid.verify(data, &sign));
+        assert_eq!(invalid.try_verify(data, &sign).unwrap_err().code(), BuckyErrorCode::InvalidParam);
+        assert!(invalid.encrypt_data(data).is_err());
+        assert!(invalid.gen_aeskey_and_encrypt().is_err());
+        assert!(invalid.to_spki_der().is_err());
+        assert!(invalid.to_pkcs1_der().is_err());
+        assert!(invalid.to_vec().is_err());
+        assert_eq!(invalid.key_size(), 0);
+        assert!(invalid.key_id().is_err());
     }
 }
@@ -0,0 +1,553 @@
+use crate::*;
+
+use async_std::io::{Read, ReadExt, Write, WriteExt};
+use async_std::net::{SocketAddr, TcpListener, TcpStream};
+use async_std::stream::StreamExt;
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+use std::sync::Arc;
+#[cfg(unix)]
+use async_std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::Path;
+
+// 远程签名协议，每个请求/响应都是一帧: version(1) | len(u32) | raw_encode(body)
+// 同一个连接上的请求按顺序处理，一个请求对应一个响应
+const REMOTE_SIGNER_PROTOCOL_VERSION: u8 = 1u8;
+const REMOTE_SIGNER_FRAME_HEADER_SIZE: usize = 5;
+// 单帧的最大长度，避免对端发送超大的长度导致内存耗尽
+pub const REMOTE_SIGNER_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+const REQUEST_CODE_PUBLIC_KEY: u8 = 0u8;
+const REQUEST_CODE_SIGN: u8 = 1u8;
+
+const RESPONSE_CODE_PUBLIC_KEY: u8 = 0u8;
+const RESPONSE_CODE_SIGN: u8 = 1u8;
+const RESPONSE_CODE_ERROR: u8 = 2u8;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RemoteSignerRequest {
+    PublicKey,
+    Sign(Vec<u8>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RemoteSignerResponse {
+    PublicKey(PublicKey),
+    Sign(Signature),
+    Error(BuckyErrorCode, String),
+}
+
+// 变长字节数组: len(u32) | bytes，不使用Vec<u8>逐字节的编码
+fn bytes_raw_measure(data: &[u8]) -> usize {
+    u32::raw_bytes().unwrap() + data.len()
+}
+
+fn bytes_raw_encode<'a>(data: &[u8], buf: &'a mut [u8], purpose: &Option<RawEncodePurpose>) -> BuckyResult<&'a mut [u8]> {
+    let buf = (data.len() as u32).raw_encode(buf, purpose)?;
+    if buf.len() < data.len() {
+        return Err(BuckyError::new(
+            BuckyErrorCode::OutOfLimit,
+            "not enough buffer for remote signer bytes",
+        ));
+    }
+    buf[..data.len()].copy_from_slice(data);
+    Ok(&mut buf[data.len()..])
+}
+
+fn bytes_raw_decode(buf: &[u8]) -> BuckyResult<(&[u8], &[u8])> {
+    let (len, buf) = u32::raw_decode(buf)?;
+    let len = len as usize;
+    if buf.len() < len {
+        return Err(BuckyError::new(
+            BuckyErrorCode::OutOfLimit,
+            "not enough buffer for remote signer bytes",
+        ));
+    }
+    Ok(buf.split_at(len))
+}
+
+impl RawEncode for RemoteSignerRequest {
+    fn raw_measure(&self, _purpose: &Option<RawEncodePurpose>) -> Result<usize, BuckyError> {
+        let size = match self {
+            Self::PublicKey => 0,
+            Self::Sign(data) => bytes_raw_measure(data),
+        };
+        Ok(u8::raw_bytes().unwrap() + size)
+    }
+
+    fn raw_encode<'a>(
+        &self,
+        buf: &'a mut [u8],
+        purpose: &Option<RawEncodePurpose>,
+    ) -> Result<&'a mut [u8], BuckyError> {
+        match self {
+            Self::PublicKey => REQUEST_CODE_PUBLIC_KEY.raw_encode(buf, purpose),
+            Self::Sign(data) => {
+                let buf = REQUEST_CODE_SIGN.raw_encode(buf, purpose)?;
+                bytes_raw_encode(data, buf, purpose)
+            }
+        }
+    }
+}
+
+impl<'de> RawDecode<'de> for RemoteSignerRequest {
+    fn raw_decode(buf: &'de [u8]) -> Result<(Self, &'de [u8]), BuckyError> {
+        let (code, buf) = u8::raw_decode(buf)?;
+        match code {
+            REQUEST_CODE_PUBLIC_KEY => Ok((Self::PublicKey, buf)),
+            REQUEST_CODE_SIGN => {
+                let (data, buf) = bytes_raw_decode(buf)?;
+                Ok((Self::Sign(data.to_vec()), buf))
+            }
+            _ => Err(BuckyError::new(
+                BuckyErrorCode::InvalidFormat,
+                format!("invalid remote signer request code: {}", code),
+            )),
+        }
+    }
+}
+
+impl RawEncode for RemoteSignerResponse {
+    fn raw_measure(&self, purpose: &Option<RawEncodePurpose>) -> Result<usize, BuckyError> {
+        let size = match self {
+            Self::PublicKey(public_key) => public_key.raw_measure(purpose)?,
+            Self::Sign(sign) => sign.raw_measure(purpose)?,
+            Self::Error(_, msg) => u16::raw_bytes().unwrap() + bytes_raw_measure(msg.as_bytes()),
+        };
+        Ok(u8::raw_bytes().unwrap() + size)
+    }
+
+    fn raw_encode<'a>(
+        &self,
+        buf: &'a mut [u8],
+        purpose: &Option<RawEncodePurpose>,
+    ) -> Result<&'a mut [u8], BuckyError> {
+        match self {
+            Self::PublicKey(public_key) => {
+                let buf = RESPONSE_CODE_PUBLIC_KEY.raw_encode(buf, purpose)?;
+                public_key.raw_encode(buf, purpose)
+            }
+            Self::Sign(sign) => {
+                let buf = RESPONSE_CODE_SIGN.raw_encode(buf, purpose)?;
+                sign.raw_encode(buf, purpose)
+            }
+            Self::Error(code, msg) => {
+                let buf = RESPONSE_CODE_ERROR.raw_encode(buf, purpose)?;
+                let buf = code.as_u16().raw_encode(buf, purpose)?;
+                bytes_raw_encode(msg.as_bytes(), buf, purpose)
+            }
+        }
+    }
+}
+
+impl<'de> RawDecode<'de> for RemoteSignerResponse {
+    fn raw_decode(buf: &'de [u8]) -> Result<(Self, &'de [u8]), BuckyError> {
+        let (code, buf) = u8::raw_decode(buf)?;
+        match code {
+            RESPONSE_CODE_PUBLIC_KEY => {
+                let (public_key, buf) = PublicKey::raw_decode(buf)?;
+                Ok((Self::PublicKey(public_key), buf))
+            }
+            RESPONSE_CODE_SIGN => {
+                let (sign, buf) = Signature::raw_decode(buf)?;
+                Ok((Self::Sign(sign), buf))
+            }
+            RESPONSE_CODE_ERROR => {
+                let (code, buf) = u16::raw_decode(buf)?;
+                let (msg, buf) = bytes_raw_decode(buf)?;
+                let msg = String::from_utf8_lossy(msg).into_owned();
+                Ok((Self::Error(BuckyErrorCode::from(code), msg), buf))
+            }
+            _ => Err(BuckyError::new(
+                BuckyErrorCode::InvalidFormat,
+                format!("invalid remote signer response code: {}", code),
+            )),
+        }
+    }
+}
+
+async fn write_frame<T: RawEncode, W: Write + Unpin>(stream: &mut W, body: &T) -> BuckyResult<()> {
+    let body = body.to_vec()?;
+    if body.len() > REMOTE_SIGNER_MAX_FRAME_SIZE {
+        let msg = format!("remote signer frame too large: {}", body.len());
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+    }
+
+    let mut frame = Vec::with_capacity(REMOTE_SIGNER_FRAME_HEADER_SIZE + body.len());
+    frame.push(REMOTE_SIGNER_PROTOCOL_VERSION);
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&body);
+    stream.write_all(&frame).await?;
+    stream.flush().await?;
+
+    Ok(())
+}
+
+// 在帧边界上对端关闭连接时返回None
+async fn read_frame<R: Read + Unpin>(stream: &mut R) -> BuckyResult<Option<Vec<u8>>> {
+    let mut header = [0u8; REMOTE_SIGNER_FRAME_HEADER_SIZE];
+    if stream.read(&mut header[..1]).await? == 0 {
+        return Ok(None);
+    }
+    stream.read_exact(&mut header[1..]).await?;
+
+    if header[0] != REMOTE_SIGNER_PROTOCOL_VERSION {
+        let msg = format!("unsupport remote signer protocol version: {}", header[0]);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+    }
+
+    let len = u32::from_be_bytes(header[1..].try_into().unwrap()) as usize;
+    if len > REMOTE_SIGNER_MAX_FRAME_SIZE {
+        let msg = format!("remote signer frame too large: {}", len);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+    }
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+fn check_loopback(addr: &SocketAddr) -> BuckyResult<()> {
+    if !addr.ip().is_loopback() {
+        let msg = format!("remote signer only support loopback address: {}", addr);
+        error!("{}", msg);
+        return Err(BuckyError::new(BuckyErrorCode::PermissionDenied, msg));
+    }
+    Ok(())
+}
+
+// 远程签名的服务端，把任意Signer通过本地socket提供给其它进程使用
+pub struct RemoteSignerServer<S: Signer + 'static> {
+    signer: Arc<S>,
+}
+
+impl<S: Signer + 'static> Clone for RemoteSignerServer<S> {
+    fn clone(&self) -> Self {
+        Self {
+            signer: self.signer.clone(),
+        }
+    }
+}
+
+impl<S: Signer + 'static> RemoteSignerServer<S> {
+    pub fn new(signer: S) -> Self {
+        Self {
+            signer: Arc::new(signer),
+        }
+    }
+
+    // 只接受loopback地址，accept出错时只记录日志，不会主动返回
+    pub async fn serve_tcp(&self, listener: TcpListener) -> BuckyResult<()> {
+        check_loopback(&listener.local_addr()?)?;
+
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            // accept的错误可能是暂时的(比如文件句柄耗尽)，不能因此停止服务
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("remote signer accept error: {}", e);
+                    continue;
+                }
+            };
+            let peer = match stream.peer_addr() {
+                Ok(peer) => peer,
+                Err(e) => {
+                    error!("get remote signer peer addr error: {}", e);
+                    continue;
+                }
+            };
+            if check_loopback(&peer).is_err() {
+                continue;
+            }
+
+            let server = self.clone();
+            async_std::task::spawn(async move {
+                if let Err(e) = server.serve_connection(stream).await {
+                    error!("remote signer connection error: peer={}, {}", peer, e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub async fn serve_unix(&self, listener: UnixListener) -> BuckyResult<()> {
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("remote signer accept error: {}", e);
+                    continue;
+                }
+            };
+            let server = self.clone();
+            async_std::task::spawn(async move {
+                if let Err(e) = server.serve_connection(stream).await {
+                    error!("remote signer connection error: {}", e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    // 处理单个连接上的请求，直到对端关闭连接
+    pub async fn serve_connection<T: Read + Write + Unpin>(&self, mut stream: T) -> BuckyResult<()> {
+        while let Some(body) = read_frame(&mut stream).await? {
+            let resp = match RemoteSignerRequest::clone_from_slice(&body) {
+                Ok(RemoteSignerRequest::PublicKey) => {
+                    RemoteSignerResponse::PublicKey(self.signer.public_key().clone())
+                }
+                Ok(RemoteSignerRequest::Sign(data)) => match self.signer.sign(&data).await {
+                    Ok(sign) => RemoteSignerResponse::Sign(sign),
+                    Err(e) => RemoteSignerResponse::Error(e.code(), e.msg().to_owned()),
+                },
+                Err(e) => RemoteSignerResponse::Error(e.code(), e.msg().to_owned()),
+            };
+
+            write_frame(&mut stream, &resp).await?;
+        }
+
+        Ok(())
+    }
+}
+
+trait RemoteSignerStream: Read + Write + Unpin + Send + Sync {}
+
+impl<T: Read + Write + Unpin + Send + Sync> RemoteSignerStream for T {}
+
+// 协议里请求和响应没有id，一次调用没有完整的读到响应(出错或者被取消)时，
+// 连接上可能残留着旧的响应，之后的调用会读到错位的结果，所以这种连接不能再使用
+struct RemoteSignerConnection {
+    stream: Box<dyn RemoteSignerStream>,
+    broken: bool,
+}
+
+// 远程签名的客户端，连接时获取并缓存公钥
+// 一次调用失败或者被取消后连接不再可用，后续的调用都返回ConnectionAborted，需要重新连接
+pub struct RemoteSigner {
+    conn: Mutex<RemoteSignerConnection>,
+    public_key: PublicKey,
+}
+
+impl RemoteSigner {
+    pub async fn connect_tcp(addr: SocketAddr) -> BuckyResult<Self> {
+        check_loopback(&addr)?;
+        let stream = TcpStream::connect(addr).await?;
+        Self::from_stream(stream).await
+    }
+
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<Path>) -> BuckyResult<Self> {
+        let stream = UnixStream::connect(path.as_ref()).await?;
+        Self::from_stream(stream).await
+    }
+
+    pub async fn from_stream<T: Read + Write + Unpin + Send + Sync + 'static>(stream: T) -> BuckyResult<Self> {
+        let mut stream: Box<dyn RemoteSignerStream> = Box::new(stream);
+        let public_key = match Self::call(&mut stream, &RemoteSignerRequest::PublicKey).await? {
+            RemoteSignerResponse::PublicKey(public_key) => public_key,
+            resp => return Err(Self::unexpected_response(resp)),
+        };
+
+        Ok(Self {
+            conn: Mutex::new(RemoteSignerConnection {
+                stream,
+                broken: false,
+            }),
+            public_key,
+        })
+    }
+
+    async fn call(stream: &mut Box<dyn RemoteSignerStream>, req: &RemoteSignerRequest) -> BuckyResult<RemoteSignerResponse> {
+        write_frame(stream, req).await?;
+        match read_frame(stream).await? {
+            Some(body) => RemoteSignerResponse::clone_from_slice(&body),
+            None => {
+                let msg = "remote signer connection closed".to_owned();
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::ConnectionAborted, msg))
+            }
+        }
+    }
+
+    // 先标记为broken，只有完整的读到了响应才恢复，这样future中途被drop时连接也保持broken状态
+    async fn call_checked(conn: &mut RemoteSignerConnection, req: &RemoteSignerRequest) -> BuckyResult<RemoteSignerResponse> {
+        if conn.broken {
+            let msg = "remote signer connection is broken by previous failed call".to_owned();
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::ConnectionAborted, msg));
+        }
+
+        conn.broken = true;
+        let resp = Self::call(&mut conn.stream, req).await?;
+        conn.broken = false;
+
+        Ok(resp)
+    }
+
+    fn unexpected_response(resp: RemoteSignerResponse) -> BuckyError {
+        match resp {
+            RemoteSignerResponse::Error(code, msg) => BuckyError::new(code, msg),
+            resp => {
+                let msg = format!("unexpected remote signer response: {:?}", resp);
+                error!("{}", msg);
+                BuckyError::new(BuckyErrorCode::InvalidData, msg)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    async fn sign(&self, data: &[u8]) -> BuckyResult<Signature> {
+        let mut conn = self.conn.lock().await;
+        let sign = match Self::call_checked(&mut conn, &RemoteSignerRequest::Sign(data.to_vec())).await? {
+            RemoteSignerResponse::Sign(sign) => sign,
+            resp => return Err(Self::unexpected_response(resp)),
+        };
+
+        // 不信任服务端返回的签名，用缓存的公钥校验
+        if let Err(e) = self.public_key.try_verify(data, &sign) {
+            let msg = format!("remote signer returned invalid signature: {}", e);
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg));
+        }
+
+        Ok(sign)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use async_std::net::TcpListener;
+
+    #[test]
+    fn remote_signer_codec() {
+        let reqs = vec![
+            RemoteSignerRequest::PublicKey,
+            RemoteSignerRequest::Sign(vec![]),
+            RemoteSignerRequest::Sign(vec![1u8; 100000]),
+        ];
+        for req in reqs {
+            let buf = req.to_vec().unwrap();
+            assert_eq!(RemoteSignerRequest::clone_from_slice(&buf).unwrap(), req);
+        }
+
+        let sk = PrivateKey::generate_secp256k1().unwrap();
+        let resps = vec![
+            RemoteSignerResponse::PublicKey(sk.public()),
+            RemoteSignerResponse::Sign(sk.sign(b"remote").unwrap()),
+            RemoteSignerResponse::Error(BuckyErrorCode::NotSupport, "not support".to_owned()),
+        ];
+        for resp in resps {
+            let buf = resp.to_vec().unwrap();
+            assert_eq!(RemoteSignerResponse::clone_from_slice(&buf).unwrap(), resp);
+        }
+
+        assert!(RemoteSignerRequest::clone_from_slice(&[9u8]).is_err());
+    }
+
+    #[test]
+    fn remote_signer_tcp() {
+        async_std::task::block_on(async {
+            let sk = PrivateKey::generate_secp256k1().unwrap();
+            let pk = sk.public();
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = RemoteSignerServer::new(LocalSigner::new(sk));
+            async_std::task::spawn(async move {
+                server.serve_tcp(listener).await.unwrap();
+            });
+
+            let signer = RemoteSigner::connect_tcp(addr).await.unwrap();
+            assert_eq!(signer.public_key(), &pk);
+            for data in [&b""[..], b"remote signer", &[7u8; 70000][..]] {
+                let sign = signer.sign(data).await.unwrap();
+                assert!(pk.verify(data, &sign));
+            }
+
+            // 不允许连接非loopback地址
+            let err = RemoteSigner::connect_tcp("10.0.0.1:1234".parse().unwrap()).await.err().unwrap();
+            assert_eq!(err.code(), BuckyErrorCode::PermissionDenied);
+        });
+    }
+
+    #[test]
+    fn remote_signer_broken_connection() {
+        use super::{read_frame, write_frame};
+        use std::time::Duration;
+
+        async_std::task::block_on(async {
+            let sk = PrivateKey::generate_secp256k1().unwrap();
+            let pk = sk.public();
+            let other = PrivateKey::generate_secp256k1().unwrap();
+
+            // 返回其它密钥的签名，第二个签名请求不响应
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            async_std::task::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                read_frame(&mut stream).await.unwrap().unwrap();
+                write_frame(&mut stream, &RemoteSignerResponse::PublicKey(pk)).await.unwrap();
+
+                read_frame(&mut stream).await.unwrap().unwrap();
+                let sign = other.sign(b"remote signer").unwrap();
+                write_frame(&mut stream, &RemoteSignerResponse::Sign(sign)).await.unwrap();
+
+                read_frame(&mut stream).await.unwrap().unwrap();
+                async_std::task::sleep(Duration::from_secs(3600)).await;
+            });
+
+            let signer = RemoteSigner::connect_tcp(addr).await.unwrap();
+            let err = signer.sign(b"remote signer").await.unwrap_err();
+            assert_eq!(err.code(), BuckyErrorCode::InvalidSignature);
+
+            // 调用被取消后连接不能再使用
+            let ret = async_std::future::timeout(Duration::from_millis(200), signer.sign(b"remote signer")).await;
+            assert!(ret.is_err());
+            let err = signer.sign(b"remote signer").await.unwrap_err();
+            assert_eq!(err.code(), BuckyErrorCode::ConnectionAborted);
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_signer_unix() {
+        use async_std::os::unix::net::UnixListener;
+
+        async_std::task::block_on(async {
+            let path = std::env::temp_dir().join(format!("bucky-remote-signer-{}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+
+            // 签名出错时错误码透传给客户端
+            let sk = PrivateKey::generate_x25519().unwrap();
+            let pk = sk.public();
+            let listener = UnixListener::bind(&path).await.unwrap();
+            let server = RemoteSignerServer::new(LocalSigner::new(sk));
+            async_std::task::spawn(async move {
+                server.serve_unix(listener).await.unwrap();
+            });
+
+            let signer = RemoteSigner::connect_unix(&path).await.unwrap();
+            assert_eq!(signer.public_key(), &pk);
+            let err = signer.sign(b"remote signer").await.unwrap_err();
+            assert_eq!(err.code(), BuckyErrorCode::NotSupport);
+
+            let _ = std::fs::remove_file(&path);
+        });
+    }
+}
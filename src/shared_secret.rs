@@ -0,0 +1,42 @@
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// 密钥协商得到的原始共享密钥
+// x25519为协商结果的32字节，secp256k1和p256为共享点的x坐标(32字节)，未经过任何kdf处理
+#[derive(Clone, Eq, PartialEq)]
+pub struct SharedSecret(Vec<u8>);
+
+// 避免共享密钥被日志打印出来
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[Protected SharedSecret]")
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SharedSecret {}
+
+impl From<Vec<u8>> for SharedSecret {
+    fn from(v: Vec<u8>) -> Self {
+        Self(v)
+    }
+}
+
+impl SharedSecret {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
@@ -1,7 +1,7 @@
 use generic_array::typenum::{marker_traits::Unsigned, U16, U32, U64, U96};
 use generic_array::GenericArray;
-use std::ptr::slice_from_raw_parts;
 use bucky_time::bucky_time_now;
+use libsecp256k1 as secp256k1;
 
 use crate::*;
 
@@ -14,12 +14,48 @@ impl Default for Signature {
     }
 }
 
+// rsa签名使用的填充方式，默认pkcs1 v1.5和旧版本兼容
+// 其余类型的密钥只支持Pkcs1v15(即各自默认的签名方式)
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum RsaSignPadding {
+    #[default]
+    Pkcs1v15,
+    PssSha256,
+}
+
+// 变长rsa签名的编码: SIGN_TYPE_RSA_* | u16长度 | 签名
+// 不占用KEY_TYPE_*的编码空间
+pub(crate) const SIGN_TYPE_RSA_PSS: u8 = 0x10u8;
+pub(crate) const SIGN_TYPE_RSA_PKCS1V15: u8 = 0x11u8;
+
+// 可恢复的secp256k1签名: SIGN_TYPE_SECP256K1_RECOVERABLE | 签名(64) | recovery id(1)
+pub(crate) const SIGN_TYPE_SECP256K1_RECOVERABLE: u8 = 0x12u8;
+
+// BIP340 schnorr签名: SIGN_TYPE_SECP256K1_SCHNORR | 签名(64)
+pub(crate) const SIGN_TYPE_SECP256K1_SCHNORR: u8 = 0x13u8;
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum SignData {
     Rsa1024(GenericArray<u32, U32>),
     Rsa2048(GenericArray<u32, U64>),
     Rsa3072(GenericArray<u32, U96>),
     Ecc(GenericArray<u32, U16>),
+    Ed25519(GenericArray<u32, U16>),
+    P256(GenericArray<u32, U16>),
+    // 任意长度的rsa-pss签名
+    RsaPss(Vec<u8>),
+    // 1024/2048/3072以外长度的rsa pkcs1 v1.5签名
+    Rsa(Vec<u8>),
+    // 带recovery id的secp256k1签名，可以从签名恢复出公钥
+    EccRecoverable(GenericArray<u32, U16>, u8),
+    // secp256k1密钥的BIP340 schnorr签名
+    Schnorr(GenericArray<u32, U16>),
+}
+
+// 定长签名按本机字节序存放在u32数组里，按字节读取
+// 返回的切片和输入的生命周期绑定，u8没有对齐要求，任意字节都是合法的u8
+fn u32_slice_as_bytes(v: &[u32]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(v.as_ptr() as *const u8, std::mem::size_of_val(v)) }
 }
 
 impl SignData {
@@ -29,37 +65,75 @@ impl SignData {
             Self::Rsa2048(_) => "rsa2048",
             Self::Rsa3072(_) => "rsa3072",
             Self::Ecc(_) => "ecc",
+            Self::Ed25519(_) => "ed25519",
+            Self::P256(_) => "p256",
+            Self::RsaPss(_) => "rsa_pss",
+            Self::Rsa(_) => "rsa",
+            Self::EccRecoverable(..) => "ecc_recoverable",
+            Self::Schnorr(_) => "schnorr",
         }
     }
 
-    pub fn as_slice<'a>(&self) -> &'a [u8] {
-        let sign_slice = match self {
-            SignData::Rsa1024(sign) => unsafe {
-                &*slice_from_raw_parts(
-                    sign.as_ptr() as *const u8,
-                    std::mem::size_of::<u32>() * U32::to_usize(),
-                )
-            },
-            SignData::Rsa2048(sign) => unsafe {
-                &*slice_from_raw_parts(
-                    sign.as_ptr() as *const u8,
-                    std::mem::size_of::<u32>() * U64::to_usize(),
-                )
-            },
-            SignData::Rsa3072(sign) => unsafe {
-                &*slice_from_raw_parts(
-                    sign.as_ptr() as *const u8,
-                    std::mem::size_of::<u32>() * U96::to_usize(),
-                )
-            },
-            SignData::Ecc(sign) => unsafe {
-                &*slice_from_raw_parts(
-                    sign.as_ptr() as *const u8,
-                    std::mem::size_of::<u32>() * U16::to_usize(),
-                )
-            },
-        };
-        sign_slice
+    pub fn recovery_id(&self) -> Option<u8> {
+        match self {
+            Self::EccRecoverable(_, recovery_id) => Some(*recovery_id),
+            _ => None,
+        }
+    }
+
+    // 和as_slice一致，按本机字节序存放，sign的长度必须是N * 4
+    fn to_array<N: generic_array::ArrayLength<u32>>(sign: &[u8]) -> GenericArray<u32, N> {
+        GenericArray::from_exact_iter(
+            sign.chunks_exact(4)
+                .map(|v| u32::from_ne_bytes(v.try_into().unwrap())),
+        )
+        .unwrap()
+    }
+
+    // 64字节的签名(secp256k1/ed25519/p256/schnorr)转换为内部的存储格式
+    pub(crate) fn from_64_bytes(sign: &[u8; 64]) -> GenericArray<u32, U16> {
+        Self::to_array(sign)
+    }
+
+    // from_64_bytes的逆操作，得到编码用的64字节签名
+    pub(crate) fn to_64_bytes(sign: &GenericArray<u32, U16>) -> [u8; 64] {
+        let mut ret = [0u8; 64];
+        for (dst, v) in ret.chunks_exact_mut(4).zip(sign.iter()) {
+            dst.copy_from_slice(&v.to_ne_bytes());
+        }
+        ret
+    }
+
+    // 1024/2048/3072保持原来的定长编码，其余长度使用变长编码
+    pub(crate) fn from_rsa_pkcs1v15(sign: Vec<u8>) -> Self {
+        match sign.len() {
+            RSA_KEY_BYTES => Self::Rsa1024(Self::to_array(&sign)),
+            RSA2048_KEY_BYTES => Self::Rsa2048(Self::to_array(&sign)),
+            RSA3072_KEY_BYTES => Self::Rsa3072(Self::to_array(&sign)),
+            _ => Self::Rsa(sign),
+        }
+    }
+
+    pub fn rsa_sign_padding(&self) -> Option<RsaSignPadding> {
+        match self {
+            Self::Rsa1024(_) | Self::Rsa2048(_) | Self::Rsa3072(_) | Self::Rsa(_) => Some(RsaSignPadding::Pkcs1v15),
+            Self::RsaPss(_) => Some(RsaSignPadding::PssSha256),
+            _ => None,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            SignData::Rsa1024(sign) => u32_slice_as_bytes(sign.as_slice()),
+            SignData::Rsa2048(sign) => u32_slice_as_bytes(sign.as_slice()),
+            SignData::Rsa3072(sign) => u32_slice_as_bytes(sign.as_slice()),
+            SignData::Ecc(sign)
+            | SignData::Ed25519(sign)
+            | SignData::P256(sign)
+            | SignData::EccRecoverable(sign, _)
+            | SignData::Schnorr(sign) => u32_slice_as_bytes(sign.as_slice()),
+            SignData::RsaPss(sign) | SignData::Rsa(sign) => sign.as_slice(),
+        }
     }
 }
 
@@ -84,13 +158,47 @@ impl Signature {
         &self.sign
     }
 
-    pub fn as_slice<'a>(&self) -> &'a [u8] {
+    pub fn as_slice(&self) -> &[u8] {
         self.sign.as_slice()
     }
 
     pub fn sign_time(&self) -> u64 {
         self.sign_time
     }
+
+    // 从可恢复的secp256k1签名恢复出签名者的公钥，data是签名时的原始数据
+    pub fn recover_public_key(&self, data: &[u8]) -> BuckyResult<PublicKey> {
+        let recovery_id = match &self.sign {
+            SignData::EccRecoverable(_, recovery_id) => *recovery_id,
+            _ => {
+                let msg = format!("recover public key from {} signature not support!", self.sign.sign_type());
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        };
+
+        let hash = hash_data_with_time(data, self.sign_time);
+        let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
+
+        let sign = secp256k1::Signature::parse_standard_slice(self.as_slice()).map_err(|e| {
+            let msg = format!("parse secp256k1 signature error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+        let recovery_id = secp256k1::RecoveryId::parse(recovery_id).map_err(|e| {
+            let msg = format!("invalid secp256k1 recovery id: {}, {}", recovery_id, e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        let public_key = secp256k1::recover(&ctx, &sign, &recovery_id).map_err(|e| {
+            let msg = format!("recover secp256k1 public key error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::CryptoError, msg)
+        })?;
+
+        Ok(PublicKey::Secp256k1(public_key))
+    }
 }
 
 impl RawEncode for Signature {
@@ -101,13 +209,17 @@ impl RawEncode for Signature {
         // sign_data: Vec<u8>
         size = size
             + u8::raw_bytes().unwrap()
-            + std::mem::size_of::<u32>()
-                * match self.sign {
-                    SignData::Rsa1024(_) => U32::to_usize(),
-                    SignData::Rsa2048(_) => U64::to_usize(),
-                    SignData::Rsa3072(_) => U96::to_usize(),
-                    SignData::Ecc(_) => U16::to_usize(),
-                };
+            + match &self.sign {
+                SignData::Rsa1024(_) => std::mem::size_of::<u32>() * U32::to_usize(),
+                SignData::Rsa2048(_) => std::mem::size_of::<u32>() * U64::to_usize(),
+                SignData::Rsa3072(_) => std::mem::size_of::<u32>() * U96::to_usize(),
+                SignData::Ecc(_) => std::mem::size_of::<u32>() * U16::to_usize(),
+                SignData::Ed25519(_) => std::mem::size_of::<u32>() * U16::to_usize(),
+                SignData::P256(_) => std::mem::size_of::<u32>() * U16::to_usize(),
+                SignData::RsaPss(sign) | SignData::Rsa(sign) => u16::raw_bytes().unwrap() + sign.len(),
+                SignData::EccRecoverable(..) => std::mem::size_of::<u32>() * U16::to_usize() + u8::raw_bytes().unwrap(),
+                SignData::Schnorr(_) => std::mem::size_of::<u32>() * U16::to_usize(),
+            };
 
         Ok(size)
     }
@@ -133,7 +245,7 @@ impl RawEncode for Signature {
         let buf = self.sign_time.raw_encode(buf, purpose)?;
 
         // sign_data: Vec<u8>
-        let buf = match self.sign {
+        let buf = match &self.sign {
             SignData::Rsa1024(sign) => {
                 let buf = KEY_TYPE_RSA.raw_encode(buf, purpose)?;
                 let bytes = std::mem::size_of::<u32>() * U32::to_usize();
@@ -182,6 +294,45 @@ impl RawEncode for Signature {
                 }
                 &mut buf[bytes..]
             }
+            SignData::Ed25519(sign) => {
+                let buf = KEY_TYPE_ED25519.raw_encode(buf, purpose)?;
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                buf[..bytes].copy_from_slice(&SignData::to_64_bytes(sign));
+                &mut buf[bytes..]
+            }
+            SignData::P256(sign) => {
+                let buf = KEY_TYPE_P256.raw_encode(buf, purpose)?;
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                buf[..bytes].copy_from_slice(&SignData::to_64_bytes(sign));
+                &mut buf[bytes..]
+            }
+            SignData::RsaPss(sign) | SignData::Rsa(sign) => {
+                if sign.len() > u16::MAX as usize {
+                    let msg = format!("{} signature too long: {}", self.sign.sign_type(), sign.len());
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::OutOfLimit, msg));
+                }
+                let code = match self.sign {
+                    SignData::RsaPss(_) => SIGN_TYPE_RSA_PSS,
+                    _ => SIGN_TYPE_RSA_PKCS1V15,
+                };
+                let buf = code.raw_encode(buf, purpose)?;
+                let buf = (sign.len() as u16).raw_encode(buf, purpose)?;
+                buf[..sign.len()].copy_from_slice(sign);
+                &mut buf[sign.len()..]
+            }
+            SignData::EccRecoverable(sign, recovery_id) => {
+                let buf = SIGN_TYPE_SECP256K1_RECOVERABLE.raw_encode(buf, purpose)?;
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                buf[..bytes].copy_from_slice(&SignData::to_64_bytes(sign));
+                recovery_id.raw_encode(&mut buf[bytes..], purpose)?
+            }
+            SignData::Schnorr(sign) => {
+                let buf = SIGN_TYPE_SECP256K1_SCHNORR.raw_encode(buf, purpose)?;
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                buf[..bytes].copy_from_slice(&SignData::to_64_bytes(sign));
+                &mut buf[bytes..]
+            }
         };
 
         Ok(buf)
@@ -264,17 +415,85 @@ impl<'de> RawDecode<'de> for Signature {
                     ));
                 }
 
-                let mut sign = GenericArray::default();
-                unsafe {
-                    std::ptr::copy(
-                        buf.as_ptr(),
-                        sign.as_mut_slice().as_mut_ptr() as *mut u8,
-                        bytes,
-                    );
-                }
+                let sign = SignData::from_64_bytes(buf[..bytes].try_into().unwrap());
 
                 (SignData::Ecc(sign), &buf[bytes..])
             }
+            KEY_TYPE_ED25519 => {
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                if buf.len() < bytes {
+                    return Err(BuckyError::new(
+                        BuckyErrorCode::OutOfLimit,
+                        "not enough buffer for ed25519 signature",
+                    ));
+                }
+
+                let sign = SignData::from_64_bytes(buf[..bytes].try_into().unwrap());
+
+                (SignData::Ed25519(sign), &buf[bytes..])
+            }
+            KEY_TYPE_P256 => {
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                if buf.len() < bytes {
+                    return Err(BuckyError::new(
+                        BuckyErrorCode::OutOfLimit,
+                        "not enough buffer for p256 signature",
+                    ));
+                }
+
+                let sign = SignData::from_64_bytes(buf[..bytes].try_into().unwrap());
+
+                (SignData::P256(sign), &buf[bytes..])
+            }
+            SIGN_TYPE_RSA_PSS | SIGN_TYPE_RSA_PKCS1V15 => {
+                let (bytes, buf) = u16::raw_decode(buf)?;
+                let bytes = bytes as usize;
+                if buf.len() < bytes {
+                    return Err(BuckyError::new(
+                        BuckyErrorCode::OutOfLimit,
+                        "not enough buffer for rsa signature",
+                    ));
+                }
+
+                let sign = buf[..bytes].to_vec();
+                let sign = match key_type {
+                    SIGN_TYPE_RSA_PSS => SignData::RsaPss(sign),
+                    _ => SignData::Rsa(sign),
+                };
+                (sign, &buf[bytes..])
+            }
+            SIGN_TYPE_SECP256K1_RECOVERABLE => {
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                if buf.len() < bytes + 1 {
+                    return Err(BuckyError::new(
+                        BuckyErrorCode::OutOfLimit,
+                        "not enough buffer for recoverable secp256k1 signature",
+                    ));
+                }
+
+                let sign = SignData::from_64_bytes(buf[..bytes].try_into().unwrap());
+                let (recovery_id, buf) = u8::raw_decode(&buf[bytes..])?;
+                if secp256k1::RecoveryId::parse(recovery_id).is_err() {
+                    let msg = format!("invalid secp256k1 recovery id: {}", recovery_id);
+                    error!("{}", msg);
+                    return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+                }
+
+                (SignData::EccRecoverable(sign, recovery_id), buf)
+            }
+            SIGN_TYPE_SECP256K1_SCHNORR => {
+                let bytes = std::mem::size_of::<u32>() * U16::to_usize();
+                if buf.len() < bytes {
+                    return Err(BuckyError::new(
+                        BuckyErrorCode::OutOfLimit,
+                        "not enough buffer for schnorr signature",
+                    ));
+                }
+
+                let sign = SignData::from_64_bytes(buf[..bytes].try_into().unwrap());
+
+                (SignData::Schnorr(sign), &buf[bytes..])
+            }
             _ => {
                 return Err(BuckyError::new(
                     BuckyErrorCode::NotMatch,
@@ -295,13 +514,55 @@ impl<'de> RawDecode<'de> for Signature {
 
 #[cfg(test)]
 mod test {
-    use crate::{RawConvertTo, RawFrom, Signature};
+    use crate::{RawConvertTo, RawFrom, RsaSignPadding, SignData, Signature};
 
     #[test]
     fn signature() {
         let sig1 = Signature::default();
         let buf = sig1.to_vec().unwrap();
         let sig2 = Signature::clone_from_slice(&buf).unwrap();
-        assert_eq!(sig1, sig2)
+        assert_eq!(sig1, sig2);
+
+        let sig1 = Signature::new(1, SignData::RsaPss(vec![7u8; 256]));
+        let buf = sig1.to_vec().unwrap();
+        assert_eq!(buf.len(), 8 + 1 + 2 + 256);
+        let sig2 = Signature::clone_from_slice(&buf).unwrap();
+        assert_eq!(sig1, sig2);
+        assert_eq!(sig2.sign().rsa_sign_padding(), Some(RsaSignPadding::PssSha256));
+        assert_eq!(sig2.as_slice(), &[7u8; 256][..]);
+        assert!(Signature::clone_from_slice(&buf[..buf.len() - 1]).is_err());
+
+        // 定长的rsa签名保持原来的编码
+        let sign: Vec<u8> = (0..=255u8).collect();
+        let sig1 = Signature::new(1, SignData::from_rsa_pkcs1v15(sign.clone()));
+        assert!(matches!(sig1.sign(), SignData::Rsa2048(_)));
+        assert_eq!(sig1.as_slice(), sign.as_slice());
+        let buf = sig1.to_vec().unwrap();
+        assert_eq!(buf.len(), 8 + 1 + 256);
+        assert_eq!(Signature::clone_from_slice(&buf).unwrap(), sig1);
+
+        let sig1 = Signature::new(1, SignData::from_rsa_pkcs1v15(vec![9u8; 512]));
+        assert!(matches!(sig1.sign(), SignData::Rsa(_)));
+        let buf = sig1.to_vec().unwrap();
+        assert_eq!(buf.len(), 8 + 1 + 2 + 512);
+        let sig2 = Signature::clone_from_slice(&buf).unwrap();
+        assert_eq!(sig1, sig2);
+        assert_eq!(sig2.sign().rsa_sign_padding(), Some(RsaSignPadding::Pkcs1v15));
+
+        // 64字节的签名按原始字节编码
+        let sign: [u8; 64] = core::array::from_fn(|i| i as u8);
+        let arr = SignData::from_64_bytes(&sign);
+        assert_eq!(SignData::to_64_bytes(&arr), sign);
+        for sig1 in [
+            Signature::new(1, SignData::Ed25519(arr)),
+            Signature::new(1, SignData::P256(arr)),
+            Signature::new(1, SignData::Schnorr(arr)),
+            Signature::new(1, SignData::EccRecoverable(arr, 1)),
+        ] {
+            let buf = sig1.to_vec().unwrap();
+            assert_eq!(&buf[9..9 + 64], &sign[..]);
+            assert_eq!(sig1.as_slice(), &sign[..]);
+            assert_eq!(Signature::clone_from_slice(&buf).unwrap(), sig1);
+        }
     }
 }
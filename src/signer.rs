@@ -1,6 +1,7 @@
 use crate::*;
 
 use async_trait::async_trait;
+use std::sync::Arc;
 
 #[async_trait]
 pub trait Signer: Sync + Send {
@@ -18,3 +19,105 @@ impl Signer for Box<dyn Signer> {
         self.as_ref().sign(data).await
     }
 }
+
+#[async_trait]
+impl<T: Signer + ?Sized> Signer for Arc<T> {
+    fn public_key(&self) -> &PublicKey {
+        self.as_ref().public_key()
+    }
+
+    async fn sign(&self, data: &[u8]) -> BuckyResult<Signature> {
+        self.as_ref().sign(data).await
+    }
+}
+
+#[async_trait]
+impl<T: Signer + ?Sized> Signer for &T {
+    fn public_key(&self) -> &PublicKey {
+        (**self).public_key()
+    }
+
+    async fn sign(&self, data: &[u8]) -> BuckyResult<Signature> {
+        (**self).sign(data).await
+    }
+}
+
+// 使用本地私钥签名
+#[derive(Clone)]
+pub struct LocalSigner {
+    private_key: Arc<PrivateKey>,
+    public_key: PublicKey,
+}
+
+impl LocalSigner {
+    pub fn new(private_key: PrivateKey) -> Self {
+        let public_key = private_key.public();
+        Self {
+            private_key: Arc::new(private_key),
+            public_key,
+        }
+    }
+
+    pub fn private_key(&self) -> &PrivateKey {
+        &self.private_key
+    }
+}
+
+impl From<PrivateKey> for LocalSigner {
+    fn from(private_key: PrivateKey) -> Self {
+        Self::new(private_key)
+    }
+}
+
+#[async_trait]
+impl Signer for LocalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    async fn sign(&self, data: &[u8]) -> BuckyResult<Signature> {
+        match self.private_key.as_ref() {
+            // rsa签名比较耗时，放到阻塞线程池里执行，避免卡住executor
+            PrivateKey::Rsa(_) => {
+                let private_key = self.private_key.clone();
+                let data = data.to_vec();
+                async_std::task::spawn_blocking(move || private_key.sign(&data)).await
+            }
+            _ => self.private_key.sign(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::sync::Arc;
+
+    async fn sign_by<S: Signer>(signer: S, data: &[u8]) -> Signature {
+        let sign = signer.sign(data).await.unwrap();
+        assert!(signer.public_key().verify(data, &sign));
+        sign
+    }
+
+    #[test]
+    fn local_signer() {
+        async_std::task::block_on(async {
+            let data = b"local signer";
+            for sk in [PrivateKey::generate_rsa(1024).unwrap(), PrivateKey::generate_secp256k1().unwrap()] {
+                let pk = sk.public();
+                let signer = LocalSigner::new(sk);
+                assert_eq!(signer.public_key(), &pk);
+
+                sign_by(&signer, data).await;
+                let sign = sign_by(Arc::new(signer.clone()), data).await;
+                assert!(pk.verify(data, &sign));
+
+                let boxed: Box<dyn Signer> = Box::new(signer);
+                sign_by(Arc::new(boxed), data).await;
+            }
+
+            let signer = LocalSigner::from(PrivateKey::generate_x25519().unwrap());
+            assert!(signer.sign(data).await.is_err());
+        });
+    }
+}
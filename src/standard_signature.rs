@@ -0,0 +1,286 @@
+use crate::*;
+
+use libsecp256k1 as secp256k1;
+use p256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use rsa::pkcs8::der::{self, asn1::UintRef, Decode, Encode, Reader};
+
+// secp256k1和p256的标量都是32字节
+const ECDSA_SCALAR_SIZE: usize = 32;
+
+// 不带签名时间的标准签名，用来和openssl/WebCrypto等外部工具互通
+// rsa: RSASSA-PKCS1-v1_5 SHA-256
+// secp256k1/p256: ECDSA SHA-256，格式为定长的r || s(IEEE P1363)，der格式用from_der/to_der转换
+// ed25519: 对原始数据的Ed25519签名(RFC8032)
+// 和Signature是不同的类型，不能互相传入；同一个密钥不能同时用于标准签名和bucky签名，见sign_standard的说明
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StandardSignature(Vec<u8>);
+
+impl StandardSignature {
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+
+    // 解析ECDSA的der格式签名: SEQUENCE { r INTEGER, s INTEGER }
+    // 和曲线无关，只检查r/s不超过32字节，r/s是否在曲线阶内由verify_standard用对应的公钥检查
+    pub fn from_der(der: &[u8]) -> BuckyResult<Self> {
+        let decode = || -> der::Result<Vec<u8>> {
+            let mut reader = der::SliceReader::new(der)?;
+            let sign = reader.sequence(|reader| {
+                let r = UintRef::decode(reader)?;
+                let s = UintRef::decode(reader)?;
+
+                let mut sign = vec![0u8; ECDSA_SCALAR_SIZE * 2];
+                for (i, v) in [r, s].iter().enumerate() {
+                    let bytes = v.as_bytes();
+                    if bytes.len() > ECDSA_SCALAR_SIZE {
+                        return Err(der::Tag::Integer.length_error());
+                    }
+                    let end = ECDSA_SCALAR_SIZE * (i + 1);
+                    sign[end - bytes.len()..end].copy_from_slice(bytes);
+                }
+                Ok(sign)
+            })?;
+            reader.finish(sign)
+        };
+
+        let sign = decode().map_err(|e| {
+            let msg = format!("parse ecdsa der signature error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })?;
+
+        Ok(Self(sign))
+    }
+
+    // 转换为ECDSA的der格式，只对ECDSA签名(定长的r || s)有效，和曲线无关
+    pub fn to_der(&self) -> BuckyResult<Vec<u8>> {
+        if self.0.len() != ECDSA_SCALAR_SIZE * 2 {
+            let msg = format!("not a valid ecdsa signature: len={}", self.0.len());
+            error!("{}", msg);
+            return Err(BuckyError::new(BuckyErrorCode::InvalidFormat, msg));
+        }
+
+        let encode = || -> der::Result<Vec<u8>> {
+            let (r, s) = self.0.split_at(ECDSA_SCALAR_SIZE);
+            let r = UintRef::new(r)?;
+            let s = UintRef::new(s)?;
+
+            let mut buf = vec![0u8; (ECDSA_SCALAR_SIZE + 8) * 2];
+            let mut writer = der::SliceWriter::new(&mut buf);
+            writer.sequence((r.encoded_len()? + s.encoded_len()?)?, |seq| {
+                r.encode(seq)?;
+                s.encode(seq)
+            })?;
+            Ok(writer.finish()?.to_vec())
+        };
+
+        encode().map_err(|e| {
+            let msg = format!("encode ecdsa der signature error: {}", e);
+            error!("{}", msg);
+            BuckyError::new(BuckyErrorCode::InvalidFormat, msg)
+        })
+    }
+}
+
+impl From<Vec<u8>> for StandardSignature {
+    fn from(sign: Vec<u8>) -> Self {
+        Self(sign)
+    }
+}
+
+impl AsRef<[u8]> for StandardSignature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PrivateKey {
+    // 注意: 标准签名直接对调用方给出的数据签名，不带签名时间和任何域分隔
+    // 如果同一个密钥也用于sign，攻击者可以构造data || sign_time形式的数据请求标准签名，
+    // 拿到的结果就是对data的有效bucky签名。用于sign_standard的密钥必须是专用的，不能再用于sign/sign_with_padding等bucky签名
+    pub fn sign_standard(&self, data: &[u8]) -> BuckyResult<StandardSignature> {
+        let sign = match self {
+            Self::Rsa(private_key) => {
+                let hash = hash_data(data);
+                private_key.sign(
+                    rsa::Pkcs1v15Sign::new::<rsa::sha2::Sha256>(),
+                    hash.as_slice(),
+                )?
+            }
+            Self::Secp256k1(private_key) => {
+                let hash = hash_data(data);
+                let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
+                let (signature, _) = secp256k1::sign(&ctx, private_key);
+                signature.serialize().to_vec()
+            }
+            Self::Ed25519(private_key) => {
+                ed25519_dalek::Signer::sign(private_key, data).to_bytes().to_vec()
+            }
+            Self::P256(private_key) => {
+                let hash = hash_data(data);
+                let signature: p256::ecdsa::Signature = private_key
+                    .sign_prehash(hash.as_slice())
+                    .map_err(|e| {
+                        BuckyError::new(BuckyErrorCode::CryptoError, format!("{}", e))
+                    })?;
+                signature.to_bytes().to_vec()
+            }
+            Self::X25519(_) => {
+                let msg = format!("sign with private key of {} not support!", self.key_type());
+                error!("{}", msg);
+                return Err(BuckyError::new(BuckyErrorCode::NotSupport, msg));
+            }
+        };
+
+        Ok(StandardSignature(sign))
+    }
+}
+
+impl PublicKey {
+    pub fn verify_standard(&self, data: &[u8], sign: &StandardSignature) -> bool {
+        match self {
+            Self::Rsa(public_key) => {
+                let hash = hash_data(data);
+                public_key
+                    .verify(
+                        rsa::Pkcs1v15Sign::new::<rsa::sha2::Sha256>(),
+                        hash.as_slice(),
+                        sign.as_slice(),
+                    )
+                    .is_ok()
+            }
+            Self::Secp256k1(public_key) => {
+                let hash = hash_data(data);
+                let ctx = secp256k1::Message::parse(hash.as_slice().try_into().unwrap());
+                let mut sign = match secp256k1::Signature::parse_standard_slice(sign.as_slice()) {
+                    Ok(sign) => sign,
+                    Err(e) => {
+                        error!("parse secp256k1 signature error: {}", e);
+                        return false;
+                    }
+                };
+                // 外部工具(比如openssl)生成的签名不一定是low-s的
+                sign.normalize_s();
+
+                secp256k1::verify(&ctx, &sign, public_key)
+            }
+            Self::Ed25519(public_key) => {
+                let sign = match ed25519_dalek::Signature::from_slice(sign.as_slice()) {
+                    Ok(sign) => sign,
+                    Err(e) => {
+                        error!("parse ed25519 signature error: {}", e);
+                        return false;
+                    }
+                };
+
+                public_key.verify_strict(data, &sign).is_ok()
+            }
+            Self::P256(public_key) => {
+                let hash = hash_data(data);
+                let sign = match p256::ecdsa::Signature::from_slice(sign.as_slice()) {
+                    Ok(sign) => sign,
+                    Err(e) => {
+                        error!("parse p256 signature error: {}", e);
+                        return false;
+                    }
+                };
+
+                public_key.verify_prehash(hash.as_slice(), &sign).is_ok()
+            }
+            Self::X25519(_) => {
+                error!("verify with public key of {} not support!", self.key_type_str());
+                false
+            }
+            Self::Invalid => {
+                error!("verify with invalid public key!");
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    #[test]
+    fn standard_signature() {
+        let data = b"standard signature";
+        for sk in [
+            PrivateKey::generate_rsa(1024).unwrap(),
+            PrivateKey::generate_secp256k1().unwrap(),
+            PrivateKey::generate_ed25519().unwrap(),
+            PrivateKey::generate_p256().unwrap(),
+        ] {
+            let pk = sk.public();
+            let sign = sk.sign_standard(data).unwrap();
+            assert!(pk.verify_standard(data, &sign));
+            assert!(!pk.verify_standard(b"other", &sign));
+
+            // 标准签名不含时间，不能当作bucky签名校验，反之亦然
+            let bucky_sign = sk.sign(data).unwrap();
+            assert!(!pk.verify_standard(data, &StandardSignature::from(bucky_sign.as_slice().to_vec())));
+        }
+        assert!(PrivateKey::generate_x25519().unwrap().sign_standard(data).is_err());
+
+        // der格式和各曲线自己的实现一致
+        let sign = PrivateKey::generate_p256().unwrap().sign_standard(data).unwrap();
+        let der = p256::ecdsa::Signature::from_slice(sign.as_slice()).unwrap().to_der();
+        assert_eq!(sign.to_der().unwrap(), der.as_bytes());
+        assert_eq!(StandardSignature::from_der(der.as_bytes()).unwrap(), sign);
+        let sign = PrivateKey::generate_secp256k1().unwrap().sign_standard(data).unwrap();
+        let der = k256::ecdsa::Signature::from_slice(sign.as_slice()).unwrap().to_der();
+        assert_eq!(sign.to_der().unwrap(), der.as_bytes());
+        assert_eq!(StandardSignature::from_der(der.as_bytes()).unwrap(), sign);
+    }
+
+    #[test]
+    fn standard_signature_openssl() {
+        // openssl dgst -sha256 -sign生成的der格式签名，消息为"abc"
+        let pk = PublicKey::from_pem("-----BEGIN PUBLIC KEY-----
+MFYwEAYHKoZIzj0CAQYFK4EEAAoDQgAE03qILTvXgKMQBlQkHYKWuCo1AAf93qLW
+xAYbjrDOTRA/X9okqwx4AqYMCH/ImhL1xXIPsM9loPeQs06OpBi33g==
+-----END PUBLIC KEY-----").unwrap();
+        // 第一个签名是high-s的
+        for der in [
+            "30450220192a129aa91399ad130adecd3fe128dc890633934b4977f34cf60ee37be9e96d022100962b846701544639e0b9c53b2c6e0b5caf6b3f9bed119310646ecf009e8f0470",
+            "3045022100a45ffdbc55530322134ee44b4f25de2552ee3ee55e47671e3d3f4ece19e23fc602204541b1c49525c69c6ba80d3a5ea5a0495afcca892fab62e3fb9a8f6be5dc236a",
+        ] {
+            let der = hex::decode(der).unwrap();
+            let sign = StandardSignature::from_der(&der).unwrap();
+            assert_eq!(sign.to_der().unwrap(), der);
+            assert!(pk.verify_standard(b"abc", &sign));
+            assert!(!pk.verify_standard(b"abd", &sign));
+        }
+
+        let pk = PublicKey::from_pem("-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEj2S3MH0x5hlOdLzpNiDl/9Ged7Ig
+/XYIpgJr/S3a6xpk4AX4KxFhiK+Autw0CN9gbIu1sS4iSwsuQZ6dLGXwzA==
+-----END PUBLIC KEY-----").unwrap();
+        let der = hex::decode("3045022034a46738f3f1a2d900e286ffe0ed75d339c21552c9659c42b05af9cd042d299d02210085ffbe44c628c9e43db3e81d2d25fc9bf24fdde6146282aeadffb7d875fba664").unwrap();
+        assert!(pk.verify_standard(b"abc", &StandardSignature::from_der(&der).unwrap()));
+
+        // openssl pkeyutl -sign -rawin
+        let pk = PublicKey::from_pem("-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAAR+k6lK2rKpH0ODzyONBdWYZWgKhy7AIrXsBZ7fUOKg=
+-----END PUBLIC KEY-----").unwrap();
+        let sign = hex::decode("9a83523871acf8d6a15685d4f4b62a20f4a39c83609f08bc0b3ba6cb3288a523631a8216f44174f78038a11a3d4ea88b6e3632db285b658112ee578744c3630f").unwrap();
+        assert!(pk.verify_standard(b"abc", &StandardSignature::from(sign)));
+
+        assert!(StandardSignature::from(vec![0u8; 10]).to_der().is_err());
+        assert!(StandardSignature::from_der(&[0x30, 0x01]).is_err());
+        // r超过32字节
+        let mut der = vec![0x30, 0x27, 0x02, 0x21, 0x01];
+        der.extend_from_slice(&[0u8; 32]);
+        der.extend_from_slice(&[0x02, 0x02, 0x00, 0x80]);
+        assert!(StandardSignature::from_der(&der).is_err());
+        // 多余的数据
+        let mut der = hex::decode("3045022034a46738f3f1a2d900e286ffe0ed75d339c21552c9659c42b05af9cd042d299d02210085ffbe44c628c9e43db3e81d2d25fc9bf24fdde6146282aeadffb7d875fba664").unwrap();
+        der.push(0);
+        assert!(StandardSignature::from_der(&der).is_err());
+    }
+}
@@ -1,11 +1,23 @@
 use crate::*;
 
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[async_trait]
 pub trait Verifier: Send + Sync {
     fn public_key(&self) -> &PublicKey;
     async fn verify(&self, data: &[u8], sign: &Signature) -> bool;
+
+    // 失败时返回具体的原因，默认实现只能区分成功和失败，失败统一返回InvalidSignature
+    async fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        if self.verify(data, sign).await {
+            Ok(())
+        } else {
+            let msg = format!("verify {} signature failed", sign.sign().sign_type());
+            Err(BuckyError::new(BuckyErrorCode::InvalidSignature, msg))
+        }
+    }
 }
 
 #[async_trait]
@@ -22,4 +34,230 @@ impl Verifier for Box<dyn Verifier> {
     async fn verify(&self, data: &[u8], sign: &Signature) -> bool {
         self.as_ref().verify(data, sign).await
     }
+
+    async fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        self.as_ref().try_verify(data, sign).await
+    }
+}
+
+#[async_trait]
+impl<T: Verifier + ?Sized> Verifier for Arc<T> {
+    fn public_key(&self) -> &PublicKey {
+        self.as_ref().public_key()
+    }
+
+    async fn verify(&self, data: &[u8], sign: &Signature) -> bool {
+        self.as_ref().verify(data, sign).await
+    }
+
+    async fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        self.as_ref().try_verify(data, sign).await
+    }
+}
+
+#[async_trait]
+impl<T: Verifier + ?Sized> Verifier for &T {
+    fn public_key(&self) -> &PublicKey {
+        (**self).public_key()
+    }
+
+    async fn verify(&self, data: &[u8], sign: &Signature) -> bool {
+        (**self).verify(data, sign).await
+    }
+
+    async fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        (**self).try_verify(data, sign).await
+    }
+}
+
+// 使用本地公钥校验
+// rsa的公钥指数很小，校验的开销不大，所以直接在当前任务里执行
+#[derive(Clone, Debug)]
+pub struct LocalVerifier {
+    public_key: PublicKey,
+}
+
+impl LocalVerifier {
+    pub fn new(public_key: PublicKey) -> Self {
+        Self { public_key }
+    }
+}
+
+impl From<PublicKey> for LocalVerifier {
+    fn from(public_key: PublicKey) -> Self {
+        Self::new(public_key)
+    }
+}
+
+#[async_trait]
+impl Verifier for LocalVerifier {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    async fn verify(&self, data: &[u8], sign: &Signature) -> bool {
+        self.public_key.verify(data, sign)
+    }
+
+    async fn try_verify(&self, data: &[u8], sign: &Signature) -> BuckyResult<()> {
+        self.public_key.try_verify(data, sign)
+    }
+}
+
+// 校验PublicKeyValue的多签，规则见PublicKeyValue::verify
+// 输入是MultiSignature而不是单个Signature，所以不实现Verifier
+#[derive(Clone, Debug)]
+pub struct MNSignatureChecker {
+    public_key: PublicKeyValue,
+}
+
+impl MNSignatureChecker {
+    pub fn new(public_key: PublicKeyValue) -> Self {
+        Self { public_key }
+    }
+
+    pub fn public_key(&self) -> &PublicKeyValue {
+        &self.public_key
+    }
+
+    pub async fn verify(&self, data: &[u8], signs: &MultiSignature) -> bool {
+        self.public_key.verify(data, signs)
+    }
+}
+
+impl From<PublicKeyValue> for MNSignatureChecker {
+    fn from(public_key: PublicKeyValue) -> Self {
+        Self::new(public_key)
+    }
+}
+
+// 通过可恢复签名找到签名者的公钥，签名里不需要携带公钥
+// 恢复出的公钥必须在已知的公钥集合里(以key_id索引)，否则返回NotFound
+pub struct RecoverPublicKeySearch<'a> {
+    data: &'a [u8],
+    known_keys: &'a HashMap<String, PublicKey>,
+}
+
+impl<'a> RecoverPublicKeySearch<'a> {
+    pub fn new(data: &'a [u8], known_keys: &'a HashMap<String, PublicKey>) -> Self {
+        Self { data, known_keys }
+    }
+
+    pub fn search(&self, sign: &Signature) -> BuckyResult<&'a PublicKey> {
+        let public_key = sign.recover_public_key(self.data)?;
+        let key_id = public_key.key_id()?;
+        match self.known_keys.get(&key_id) {
+            Some(v) if *v == public_key => Ok(v),
+            _ => {
+                let msg = format!("recovered public key not in known keys: {}", key_id);
+                error!("{}", msg);
+                Err(BuckyError::new(BuckyErrorCode::NotFound, msg))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl PublicKeySearch for RecoverPublicKeySearch<'_> {
+    async fn search_public_key<'s>(&'s self, sign: &Signature) -> BuckyResult<&'s PublicKey> {
+        self.search(sign)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    async fn verify_by<V: Verifier>(verifier: V, data: &[u8], sign: &Signature) -> bool {
+        verifier.verify(data, sign).await
+    }
+
+    #[test]
+    fn local_verifier() {
+        async_std::task::block_on(async {
+            let data = b"local verifier";
+            let sk = PrivateKey::generate_secp256k1().unwrap();
+            let sign = sk.sign(data).unwrap();
+
+            let verifier = LocalVerifier::new(sk.public());
+            assert_eq!(verifier.public_key(), &sk.public());
+            assert!(verifier.verify(data, &sign).await);
+            assert!(!verifier.verify(b"local verifier2", &sign).await);
+            assert!(verify_by(&verifier, data, &sign).await);
+            assert!(verifier.try_verify(data, &sign).await.is_ok());
+            let err = verifier.try_verify(b"local verifier2", &sign).await.unwrap_err();
+            assert_eq!(err.code(), BuckyErrorCode::InvalidSignature);
+            let boxed: Box<dyn Verifier> = Box::new(verifier);
+            assert!(verify_by(Arc::new(boxed), data, &sign).await);
+
+            let keys: Vec<PrivateKey> = (0..3).map(|_| PrivateKey::generate_secp256k1().unwrap()).collect();
+            let checker = MNSignatureChecker::new(PublicKeyValue::MN((2, keys.iter().map(|v| v.public()).collect())));
+            let mut signs = MultiSignature::new();
+            signs.push(1, keys[1].sign(data).unwrap());
+            assert!(!checker.verify(data, &signs).await);
+            signs.push(0, keys[0].sign(data).unwrap());
+            assert!(checker.verify(data, &signs).await);
+        });
+    }
+
+    #[test]
+    fn recover_public_key() {
+        let data = b"gossip message";
+        let sk = PrivateKey::generate_secp256k1().unwrap();
+        let sign = sk.sign_recoverable(data).unwrap();
+        assert!(sign.sign().recovery_id().is_some());
+        assert_eq!(sign.recover_public_key(data).unwrap(), sk.public());
+        assert!(sk.public().verify(data, &sign));
+        assert!(!sk.public().verify(b"gossip message2", &sign));
+        assert_ne!(sign.recover_public_key(b"gossip message2").unwrap(), sk.public());
+
+        let buf = sign.to_vec().unwrap();
+        assert_eq!(buf.len(), 8 + 1 + 64 + 1);
+        let sign2 = Signature::clone_from_slice(&buf).unwrap();
+        assert_eq!(sign, sign2);
+        assert_eq!(sign2.recover_public_key(data).unwrap(), sk.public());
+
+        let mut buf = buf;
+        let len = buf.len();
+        buf[len - 1] = 4;
+        assert_eq!(Signature::clone_from_slice(&buf).unwrap_err().code(), BuckyErrorCode::InvalidFormat);
+
+        // 只有正确的recovery id能校验通过
+        let recovery_id = sign.sign().recovery_id().unwrap();
+        for id in 0..=u8::MAX {
+            let sign2 = Signature::new(sign.sign_time(), SignData::EccRecoverable(SignData::from_64_bytes(sign.as_slice().try_into().unwrap()), id));
+            assert_eq!(sk.public().verify(data, &sign2), id == recovery_id);
+            if id > 3 {
+                assert_eq!(sk.public().try_verify(data, &sign2).unwrap_err().code(), BuckyErrorCode::InvalidFormat);
+            }
+        }
+
+        let sign = sk.sign(data).unwrap();
+        assert_eq!(sign.recover_public_key(data).unwrap_err().code(), BuckyErrorCode::NotSupport);
+        assert!(PrivateKey::generate_ed25519().unwrap().sign_recoverable(data).is_err());
+    }
+
+    #[test]
+    fn recover_public_key_search() {
+        let data = b"gossip message";
+        let keys: Vec<PrivateKey> = (0..3).map(|_| PrivateKey::generate_secp256k1().unwrap()).collect();
+        let known_keys: HashMap<String, PublicKey> = keys[..2]
+            .iter()
+            .map(|sk| (sk.public().key_id().unwrap(), sk.public()))
+            .collect();
+
+        let search = RecoverPublicKeySearch::new(data, &known_keys);
+        for sk in &keys[..2] {
+            let sign = sk.sign_recoverable(data).unwrap();
+            let pk = async_std::task::block_on(search.search_public_key(&sign)).unwrap();
+            assert_eq!(*pk, sk.public());
+            assert!(pk.verify(data, &sign));
+        }
+
+        let sign = keys[2].sign_recoverable(data).unwrap();
+        let err = async_std::task::block_on(search.search_public_key(&sign)).unwrap_err();
+        assert_eq!(err.code(), BuckyErrorCode::NotFound);
+    }
 }
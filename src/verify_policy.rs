@@ -0,0 +1,209 @@
+use crate::*;
+
+use bucky_time::bucky_time_now;
+use std::time::Duration;
+
+// 签名时间的校验策略，时间都是bucky time(微秒)，未设置的约束不检查
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifyPolicy {
+    max_age: Option<Duration>,
+    max_future_skew: Option<Duration>,
+    not_before: Option<u64>,
+    not_after: Option<u64>,
+    now: Option<u64>,
+}
+
+impl VerifyPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 签名时间距当前时间最多过去多久
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    // 允许签名时间比当前时间超前多少，用来容忍两端的时钟误差
+    pub fn max_future_skew(mut self, skew: Duration) -> Self {
+        self.max_future_skew = Some(skew);
+        self
+    }
+
+    // 签名时间必须在[not_before, not_after]内
+    pub fn not_before(mut self, time: u64) -> Self {
+        self.not_before = Some(time);
+        self
+    }
+
+    pub fn not_after(mut self, time: u64) -> Self {
+        self.not_after = Some(time);
+        self
+    }
+
+    // 指定校验时使用的当前时间，默认是bucky_time_now()
+    pub fn now(mut self, now: u64) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    // 只检查签名时间，不校验签名本身
+    pub fn check_sign_time(&self, sign_time: u64) -> Result<(), VerifyFailReason> {
+        let now = self.now.unwrap_or_else(bucky_time_now);
+
+        if let Some(not_before) = self.not_before {
+            if sign_time < not_before {
+                return Err(VerifyFailReason::NotYetValid { sign_time, not_before });
+            }
+        }
+        if let Some(not_after) = self.not_after {
+            if sign_time > not_after {
+                return Err(VerifyFailReason::NoLongerValid { sign_time, not_after });
+            }
+        }
+        if let Some(skew) = self.max_future_skew {
+            if sign_time > now && sign_time - now > duration_to_micros(&skew) {
+                return Err(VerifyFailReason::FromFuture { sign_time, now });
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            if now > sign_time && now - sign_time > duration_to_micros(&max_age) {
+                return Err(VerifyFailReason::Expired { sign_time, now });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn duration_to_micros(duration: &Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+// verify_with_policy失败的原因
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerifyFailReason {
+    // 签名本身无效
+    InvalidSignature,
+    // 超过了max_age
+    Expired { sign_time: u64, now: u64 },
+    // 签名时间超前当前时间超过了max_future_skew
+    FromFuture { sign_time: u64, now: u64 },
+    // 签名时间早于not_before
+    NotYetValid { sign_time: u64, not_before: u64 },
+    // 签名时间晚于not_after
+    NoLongerValid { sign_time: u64, not_after: u64 },
+}
+
+impl VerifyFailReason {
+    pub fn error_code(&self) -> BuckyErrorCode {
+        match self {
+            Self::InvalidSignature => BuckyErrorCode::InvalidSignature,
+            Self::Expired { .. } | Self::NoLongerValid { .. } => BuckyErrorCode::Expired,
+            Self::FromFuture { .. } | Self::NotYetValid { .. } => BuckyErrorCode::OutOfLimit,
+        }
+    }
+}
+
+impl std::fmt::Display for VerifyFailReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "invalid signature"),
+            Self::Expired { sign_time, now } => write!(f, "signature expired: sign_time={}, now={}", sign_time, now),
+            Self::FromFuture { sign_time, now } => write!(f, "signature from future: sign_time={}, now={}", sign_time, now),
+            Self::NotYetValid { sign_time, not_before } => write!(f, "signature not yet valid: sign_time={}, not_before={}", sign_time, not_before),
+            Self::NoLongerValid { sign_time, not_after } => write!(f, "signature no longer valid: sign_time={}, not_after={}", sign_time, not_after),
+        }
+    }
+}
+
+impl From<VerifyFailReason> for BuckyError {
+    fn from(reason: VerifyFailReason) -> Self {
+        BuckyError::new(reason.error_code(), reason.to_string())
+    }
+}
+
+impl PublicKey {
+    // 先校验签名，签名有效时sign_time才可信，再按策略检查签名时间
+    pub fn verify_with_policy(&self, data: &[u8], sign: &Signature, policy: &VerifyPolicy) -> Result<(), VerifyFailReason> {
+        if !self.verify(data, sign) {
+            return Err(VerifyFailReason::InvalidSignature);
+        }
+
+        policy.check_sign_time(sign.sign_time()).inspect_err(|reason| {
+            warn!("verify signature with policy failed: {}", reason);
+        })
+    }
+
+    // 和verify_with_policy一样，但是签名无效时返回try_verify的具体原因
+    // 签名时间不满足策略时返回VerifyFailReason::error_code对应的错误
+    pub fn try_verify_with_policy(&self, data: &[u8], sign: &Signature, policy: &VerifyPolicy) -> BuckyResult<()> {
+        self.try_verify(data, sign)?;
+        policy.check_sign_time(sign.sign_time()).map_err(|reason| {
+            let msg = format!("verify signature with policy failed: {}", reason);
+            warn!("{}", msg);
+            BuckyError::new(reason.error_code(), msg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+    use std::time::Duration;
+
+    #[test]
+    fn verify_with_policy() {
+        let sk = PrivateKey::generate_secp256k1().unwrap();
+        let pk = sk.public();
+        let data = b"policy object";
+        let sign = sk.sign(data).unwrap();
+        let sign_time = sign.sign_time();
+        let sec = 1000 * 1000;
+
+        assert!(pk.verify_with_policy(data, &sign, &VerifyPolicy::default()).is_ok());
+        assert_eq!(
+            pk.verify_with_policy(b"other", &sign, &VerifyPolicy::default()),
+            Err(VerifyFailReason::InvalidSignature)
+        );
+
+        // max age
+        let policy = VerifyPolicy::new().max_age(Duration::from_secs(60));
+        assert!(pk.verify_with_policy(data, &sign, &policy).is_ok());
+        assert!(pk.verify_with_policy(data, &sign, &policy.clone().now(sign_time + 60 * sec)).is_ok());
+        let now = sign_time + 60 * sec + 1;
+        assert_eq!(
+            pk.verify_with_policy(data, &sign, &policy.clone().now(now)),
+            Err(VerifyFailReason::Expired { sign_time, now })
+        );
+
+        // 时钟误差
+        let now = sign_time - 5 * sec;
+        let policy = VerifyPolicy::new().max_age(Duration::from_secs(60)).now(now);
+        assert!(pk.verify_with_policy(data, &sign, &policy).is_ok());
+        assert!(pk.verify_with_policy(data, &sign, &policy.clone().max_future_skew(Duration::from_secs(5))).is_ok());
+        assert_eq!(
+            pk.verify_with_policy(data, &sign, &policy.clone().max_future_skew(Duration::from_secs(4))),
+            Err(VerifyFailReason::FromFuture { sign_time, now })
+        );
+
+        // 有效期窗口
+        let policy = VerifyPolicy::new().not_before(sign_time).not_after(sign_time);
+        assert!(pk.verify_with_policy(data, &sign, &policy).is_ok());
+        assert_eq!(
+            pk.verify_with_policy(data, &sign, &VerifyPolicy::new().not_before(sign_time + 1)),
+            Err(VerifyFailReason::NotYetValid { sign_time, not_before: sign_time + 1 })
+        );
+        assert_eq!(
+            pk.verify_with_policy(data, &sign, &VerifyPolicy::new().not_after(sign_time - 1)),
+            Err(VerifyFailReason::NoLongerValid { sign_time, not_after: sign_time - 1 })
+        );
+
+        // 签名无效时不检查时间
+        let policy = VerifyPolicy::new().not_after(sign_time - 1);
+        assert_eq!(pk.verify_with_policy(b"other", &sign, &policy), Err(VerifyFailReason::InvalidSignature));
+
+        let e = BuckyError::from(VerifyFailReason::Expired { sign_time, now: sign_time + 1 });
+        assert_eq!(e.code(), BuckyErrorCode::Expired);
+    }
+}